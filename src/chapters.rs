@@ -0,0 +1,234 @@
+//Parsing of GoPro file names and grouping of chaptered videos into recordings
+//
+//The naming scheme is described by GoPro at
+//<https://community.gopro.com/s/article/GoPro-Camera-File-Naming-Convention>
+
+use crate::media::{MediaDirectory, MediaFile};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+///How a video was encoded, as given by the prefix of its file name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MediaEncoding {
+    ///`GOPR` and `GP`, used by cameras older than the HERO6
+    Legacy,
+    ///`GH`
+    Avc,
+    ///`GX`
+    Hevc,
+    ///`GS`, 360 degree video from the MAX
+    Spherical,
+}
+
+///What the name of a file says about it, e.g. `GX020123.MP4` is the second chapter of
+///video 123, encoded with HEVC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaName {
+    pub encoding: MediaEncoding,
+    ///The chapter of the recording the file holds, starting from 1
+    ///
+    ///Legacy names don't number their first chapter (`GOPR0123.MP4`) and number the rest
+    ///from 01 (`GP010123.MP4`), so those are shifted to match the other encodings
+    pub chapter: u8,
+    ///The number shared by every chapter of a recording
+    pub file_number: u16,
+    ///The extension, e.g. `MP4`
+    pub extension: String,
+}
+
+impl MediaName {
+    ///Parses a file name, returning None if it isn't a GOPR/GP/GX/GH/GS name
+    pub fn parse(name: &str) -> Option<Self> {
+        let (stem, extension) = name.split_once('.')?;
+        if stem.len() != 8 || !stem.is_ascii() {
+            return None;
+        }
+        let file_number = stem[4..].parse().ok()?;
+        let (encoding, chapter) = match &stem[..4] {
+            "GOPR" => (MediaEncoding::Legacy, 1),
+            prefix => {
+                let chapter: u8 = prefix[2..].parse().ok()?;
+                match &prefix[..2] {
+                    "GP" => (MediaEncoding::Legacy, chapter.checked_add(1)?),
+                    "GH" => (MediaEncoding::Avc, chapter),
+                    "GX" => (MediaEncoding::Hevc, chapter),
+                    "GS" => (MediaEncoding::Spherical, chapter),
+                    _ => return None,
+                }
+            }
+        };
+        Some(MediaName {
+            encoding,
+            chapter,
+            file_number,
+            extension: extension.to_string(),
+        })
+    }
+
+    ///Whether the file is a video, rather than a photo or a proxy
+    pub fn is_video(&self) -> bool {
+        matches!(self.extension.as_str(), "MP4" | "360")
+    }
+}
+
+///A video the camera split into chapters, presented as one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub encoding: MediaEncoding,
+    ///The number shared by every chapter, see [`MediaName::file_number`]
+    pub file_number: u16,
+    ///The chapters in the order they were recorded
+    pub chapters: Vec<MediaFile>,
+    ///The total duration of the chapters, once fetched by [`crate::GoProHttp::recordings`]
+    pub duration: Option<Duration>,
+    ///The hilights of every chapter, from the start of the recording, once fetched by
+    ///[`crate::GoProHttp::recordings`]
+    pub hilights: Option<Vec<Duration>>,
+}
+
+impl Recording {
+    ///Returns the total size of the chapters in bytes
+    pub fn size(&self) -> u64 {
+        self.chapters.iter().map(|chapter| chapter.size).sum()
+    }
+}
+
+///Groups the chaptered videos in a media list into recordings, ordered by file number
+///
+///Files that aren't videos with a GoPro name are left out. The durations of the
+///recordings and their hilights aren't in the media list, so they are None
+///
+///A recording can carry on into the next directory, but the camera also starts over at
+///file number 1 in a new directory once the numbers run out. So chapters are taken in
+///directory order and a chapter number that comes round again starts a new recording
+pub fn recordings(directories: &[MediaDirectory]) -> Vec<Recording> {
+    let mut chapters: BTreeMap<(u16, MediaEncoding), Vec<(u8, &MediaFile)>> = BTreeMap::new();
+    for file in directories.iter().flat_map(|directory| &directory.files) {
+        let Some(name) = MediaName::parse(&file.name).filter(MediaName::is_video) else {
+            continue;
+        };
+        chapters
+            .entry((name.file_number, name.encoding))
+            .or_default()
+            .push((name.chapter, file));
+    }
+    let mut recordings = Vec::new();
+    for ((file_number, encoding), mut files) in chapters {
+        files.sort_by(|(a, a_file), (b, b_file)| {
+            (&a_file.directory, a).cmp(&(&b_file.directory, b))
+        });
+        let mut last = None;
+        for (chapter, file) in files {
+            if last.map_or(true, |last| chapter <= last) {
+                recordings.push(Recording {
+                    encoding,
+                    file_number,
+                    chapters: Vec::new(),
+                    duration: None,
+                    hilights: None,
+                });
+            }
+            last = Some(chapter);
+            recordings.last_mut().unwrap().chapters.push(file.clone());
+        }
+    }
+    recordings
+}
+
+#[test]
+fn test_parse_media_name() {
+    let name = MediaName::parse("GX020123.MP4").unwrap();
+    assert_eq!(name.encoding, MediaEncoding::Hevc);
+    assert_eq!((name.chapter, name.file_number), (2, 123));
+    assert!(name.is_video());
+
+    let first = MediaName::parse("GOPR0042.MP4").unwrap();
+    let second = MediaName::parse("GP010042.MP4").unwrap();
+    assert_eq!((first.encoding, first.chapter), (MediaEncoding::Legacy, 1));
+    assert_eq!(
+        (second.encoding, second.chapter),
+        (MediaEncoding::Legacy, 2)
+    );
+    assert_eq!(
+        MediaName::parse("GH019999.MP4").unwrap().encoding,
+        MediaEncoding::Avc
+    );
+    assert_eq!(
+        MediaName::parse("GS010007.360").unwrap().encoding,
+        MediaEncoding::Spherical
+    );
+    assert!(!MediaName::parse("GOPR0001.JPG").unwrap().is_video());
+
+    assert_eq!(MediaName::parse("G0010003.JPG"), None);
+    assert_eq!(MediaName::parse("GL010002.LRV"), None);
+    assert_eq!(MediaName::parse("GXAB0123.MP4"), None);
+    assert_eq!(MediaName::parse("README.txt"), None);
+}
+
+#[test]
+fn test_recordings() {
+    let list = crate::media::parse_media_list(
+        r#"{"media": [{"d": "100GOPRO", "fs": [
+            {"n": "GX020123.MP4", "cre": "2", "mod": "2", "s": "200"},
+            {"n": "GOPR0001.JPG", "cre": "1", "mod": "1", "s": "5"},
+            {"n": "GP010100.MP4", "cre": "1", "mod": "1", "s": "30"},
+            {"n": "GX010123.MP4", "cre": "1", "mod": "1", "s": "400"},
+            {"n": "GOPR0100.MP4", "cre": "1", "mod": "1", "s": "40"}
+        ]}, {"d": "101GOPRO", "fs": [
+            {"n": "GX030123.MP4", "cre": "3", "mod": "3", "s": "100"},
+            {"n": "GX010124.MP4", "cre": "4", "mod": "4", "s": "10"}
+        ]}]}"#,
+    )
+    .unwrap();
+    let recordings = recordings(&list);
+    assert_eq!(recordings.len(), 3);
+
+    assert_eq!(recordings[0].encoding, MediaEncoding::Legacy);
+    let names: Vec<&str> = recordings[0]
+        .chapters
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["GOPR0100.MP4", "GP010100.MP4"]);
+
+    let long = &recordings[1];
+    let paths: Vec<String> = long.chapters.iter().map(MediaFile::path).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "100GOPRO/GX010123.MP4",
+            "100GOPRO/GX020123.MP4",
+            "101GOPRO/GX030123.MP4"
+        ]
+    );
+    assert_eq!(long.size(), 700);
+    assert_eq!((long.duration, long.hilights.as_ref()), (None, None));
+    assert_eq!(recordings[2].chapters.len(), 1);
+}
+
+#[test]
+fn test_recordings_reused_file_number() {
+    //101GOPRO starts over at file number 1 after 100GOPRO ran out of numbers
+    let list = crate::media::parse_media_list(
+        r#"{"media": [{"d": "101GOPRO", "fs": [
+            {"n": "GX010001.MP4", "cre": "9", "mod": "9", "s": "30"}
+        ]}, {"d": "100GOPRO", "fs": [
+            {"n": "GX020001.MP4", "cre": "2", "mod": "2", "s": "20"},
+            {"n": "GX010001.MP4", "cre": "1", "mod": "1", "s": "10"}
+        ]}]}"#,
+    )
+    .unwrap();
+    let recordings = recordings(&list);
+    let paths: Vec<Vec<String>> = recordings
+        .iter()
+        .map(|r| r.chapters.iter().map(MediaFile::path).collect())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            vec!["100GOPRO/GX010001.MP4", "100GOPRO/GX020001.MP4"],
+            vec!["101GOPRO/GX010001.MP4"]
+        ]
+    );
+    assert_eq!(recordings[1].size(), 30);
+}
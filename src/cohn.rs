@@ -0,0 +1,259 @@
+use crate::protobuf::{self, ResponseGeneric};
+use crate::services::{GoProControlAndQueryCharacteristics as GPCharac, ToUUID};
+use crate::wifi::CohnCredentials;
+use crate::GoPro;
+use prost::Message;
+use std::error::Error;
+
+//The feature and action IDs of the Camera on the Home Network (COHN) protobuf messages
+//
+//NOTE: The IDs and message definitions were taken directly from the GoPro Open Spec:
+//<https://gopro.github.io/OpenGoPro/ble_2_0#protobuf>
+const COMMAND_FEATURE_ID: u8 = 0xF1;
+const QUERY_FEATURE_ID: u8 = 0xF5;
+const CLEAR_CERT: [u8; 2] = [COMMAND_FEATURE_ID, 0x66];
+const CLEAR_CERT_RESPONSE: [u8; 2] = [COMMAND_FEATURE_ID, 0xE6];
+const CREATE_CERT: [u8; 2] = [COMMAND_FEATURE_ID, 0x67];
+const CREATE_CERT_RESPONSE: [u8; 2] = [COMMAND_FEATURE_ID, 0xE7];
+const GET_CERT: [u8; 2] = [QUERY_FEATURE_ID, 0x6E];
+const GET_CERT_RESPONSE: [u8; 2] = [QUERY_FEATURE_ID, 0xEE];
+const GET_STATUS: [u8; 2] = [QUERY_FEATURE_ID, 0x6F];
+const STATUS_NOTIFICATION: [u8; 2] = [QUERY_FEATURE_ID, 0xEF];
+
+///The state of the camera's connection to the home network
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum CohnNetworkState {
+    Init = 0,
+    Error = 1,
+    Exit = 2,
+    Idle = 5,
+    NetworkConnected = 27,
+    NetworkDisconnected = 28,
+    ConnectingToNetwork = 29,
+    Invalid = 30,
+}
+
+///The COHN status reported by the camera
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CohnStatus {
+    ///True if the camera has a COHN certificate
+    pub provisioned: bool,
+    pub state: CohnNetworkState,
+    pub enabled: bool,
+    ///The username for HTTPS requests to the camera
+    pub username: String,
+    ///The password for HTTPS requests to the camera
+    pub password: String,
+    ///The address of the camera on the home network
+    pub ip_address: String,
+    ///The network the camera is connected to
+    pub ssid: String,
+    pub mac_address: String,
+}
+
+///The protobuf messages exchanged with the COHN feature
+mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct RequestClearCohnCert {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct RequestCreateCohnCert {
+        #[prost(bool, optional, tag = "1")]
+        pub r#override: Option<bool>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct RequestCohnCert {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct ResponseCohnCert {
+        #[prost(enumeration = "crate::protobuf::ResultGeneric", required, tag = "1")]
+        pub result: i32,
+        #[prost(string, optional, tag = "2")]
+        pub cert: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct RequestGetCohnStatus {
+        #[prost(bool, optional, tag = "1")]
+        pub register_cohn_status: Option<bool>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct NotifyCohnStatus {
+        //0 is unprovisioned and 1 is provisioned
+        #[prost(int32, optional, tag = "1")]
+        pub status: Option<i32>,
+        #[prost(enumeration = "super::CohnNetworkState", optional, tag = "2")]
+        pub state: Option<i32>,
+        #[prost(string, optional, tag = "3")]
+        pub username: Option<String>,
+        #[prost(string, optional, tag = "4")]
+        pub password: Option<String>,
+        #[prost(string, optional, tag = "5")]
+        pub ipaddress: Option<String>,
+        #[prost(bool, optional, tag = "6")]
+        pub enabled: Option<bool>,
+        #[prost(string, optional, tag = "7")]
+        pub ssid: Option<String>,
+        #[prost(string, optional, tag = "8")]
+        pub macaddress: Option<String>,
+    }
+}
+
+impl From<proto::NotifyCohnStatus> for CohnStatus {
+    fn from(status: proto::NotifyCohnStatus) -> Self {
+        CohnStatus {
+            provisioned: status.status == Some(1),
+            state: status.state(),
+            enabled: status.enabled(),
+            username: status.username.unwrap_or_default(),
+            password: status.password.unwrap_or_default(),
+            ip_address: status.ipaddress.unwrap_or_default(),
+            ssid: status.ssid.unwrap_or_default(),
+            mac_address: status.macaddress.unwrap_or_default(),
+        }
+    }
+}
+
+///Sends a COHN command, which is written to the command characteristic
+async fn command(
+    gopro: &GoPro,
+    id: [u8; 2],
+    request: &impl Message,
+    response_id: [u8; 2],
+) -> Result<(), Box<dyn Error>> {
+    let response: ResponseGeneric = protobuf::request(
+        gopro,
+        &gopro.characteristics.command,
+        GPCharac::CommandResponse.to_uuid(),
+        id,
+        request,
+        response_id,
+    )
+    .await?;
+    protobuf::check_result(response.result())
+}
+
+///Sends a COHN query, which is written to the query characteristic
+async fn query<R: Message + Default>(
+    gopro: &GoPro,
+    id: [u8; 2],
+    request: &impl Message,
+    response_id: [u8; 2],
+) -> Result<R, Box<dyn Error>> {
+    protobuf::request(
+        gopro,
+        &gopro.characteristics.query,
+        GPCharac::QueryResponse.to_uuid(),
+        id,
+        request,
+        response_id,
+    )
+    .await
+}
+
+///Has the camera create a COHN certificate, replacing the existing one if `replace` is set
+pub(crate) async fn create_certificate(gopro: &GoPro, replace: bool) -> Result<(), Box<dyn Error>> {
+    let request = proto::RequestCreateCohnCert {
+        r#override: Some(replace),
+    };
+    command(gopro, CREATE_CERT, &request, CREATE_CERT_RESPONSE).await
+}
+
+///Has the camera delete its COHN certificate
+pub(crate) async fn clear_certificate(gopro: &GoPro) -> Result<(), Box<dyn Error>> {
+    command(
+        gopro,
+        CLEAR_CERT,
+        &proto::RequestClearCohnCert {},
+        CLEAR_CERT_RESPONSE,
+    )
+    .await
+}
+
+///Gets the camera's COHN certificate
+pub(crate) async fn certificate(gopro: &GoPro) -> Result<String, Box<dyn Error>> {
+    let response: proto::ResponseCohnCert = query(
+        gopro,
+        GET_CERT,
+        &proto::RequestCohnCert {},
+        GET_CERT_RESPONSE,
+    )
+    .await?;
+    protobuf::check_result(response.result())?;
+    response
+        .cert
+        .ok_or_else(|| "GoPro didn't send a certificate".into())
+}
+
+///Gets the camera's COHN status
+pub(crate) async fn status(gopro: &GoPro) -> Result<CohnStatus, Box<dyn Error>> {
+    let request = proto::RequestGetCohnStatus {
+        register_cohn_status: Some(false),
+    };
+    let status: proto::NotifyCohnStatus =
+        query(gopro, GET_STATUS, &request, STATUS_NOTIFICATION).await?;
+    Ok(status.into())
+}
+
+///Gets what's needed to make HTTPS requests to the camera on the home network
+pub(crate) async fn credentials(gopro: &GoPro) -> Result<CohnCredentials, Box<dyn Error>> {
+    let status = status(gopro).await?;
+    if !status.provisioned {
+        return Err("GoPro has no COHN certificate, create one first".into());
+    }
+    if status.state != CohnNetworkState::NetworkConnected {
+        return Err(format!(
+            "GoPro isn't connected to the home network: {:?}",
+            status.state
+        )
+        .into());
+    }
+    Ok(CohnCredentials {
+        username: status.username,
+        password: status.password,
+        ip_address: status.ip_address,
+        certificate: certificate(gopro).await?,
+    })
+}
+
+#[test]
+fn test_decode_cohn_status() {
+    let notification = proto::NotifyCohnStatus {
+        status: Some(1),
+        state: Some(CohnNetworkState::NetworkConnected as i32),
+        username: Some("gopro".into()),
+        password: Some("s3cret".into()),
+        ipaddress: Some("192.168.1.42".into()),
+        enabled: Some(true),
+        ssid: Some("studio".into()),
+        macaddress: Some("2474f7000000".into()),
+    };
+    let packets = protobuf::test_notifications(
+        GPCharac::QueryResponse.to_uuid(),
+        STATUS_NOTIFICATION,
+        &notification,
+    );
+    let mut acc = crate::packet::Accumulator::default();
+    let message = packets.iter().find_map(|p| acc.push(&p.value)).unwrap();
+    assert!(protobuf::is_message(&message, STATUS_NOTIFICATION));
+
+    let status: CohnStatus = protobuf::decode::<proto::NotifyCohnStatus>(&message)
+        .unwrap()
+        .into();
+    assert!(status.provisioned && status.enabled);
+    assert_eq!(status.state, CohnNetworkState::NetworkConnected);
+    assert_eq!(status.ip_address, "192.168.1.42");
+
+    // Unprovisioned cameras leave most fields out
+    let status: CohnStatus = proto::NotifyCohnStatus {
+        status: Some(0),
+        ..Default::default()
+    }
+    .into();
+    assert!(!status.provisioned);
+    assert_eq!(status.state, CohnNetworkState::Init);
+    assert!(status.username.is_empty());
+}
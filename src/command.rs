@@ -14,6 +14,8 @@ pub enum GoProCommand {
     VideoMode,
     PhotoMode,
     TimelapseMode,
+    WifiApOn,
+    WifiApOff,
 }
 
 ///Implement AsRef for GoProCommands so that relevant functions
@@ -26,6 +28,33 @@ impl AsRef<GoProCommand> for GoProCommand {
 
 use GoProCommand as GPC; //alias for conciseness
 
+impl GoProCommand {
+    ///Returns false for the commands that are meant to be sent while the
+    ///camera is encoding, and so shouldn't wait for it to be ready
+    #[cfg(feature = "query")]
+    pub(crate) fn requires_ready(&self) -> bool {
+        !matches!(self, GPC::ShutterStop | GPC::AddHilightDuringEncoding)
+    }
+
+    ///Returns the HTTP endpoint (path and query) that does the same as the command,
+    ///or None for the commands that can only be sent over BLE
+    ///
+    ///NOTE: The endpoints were taken directly from the GoPro Open Spec:
+    ///<https://gopro.github.io/OpenGoPro/http>
+    #[cfg(feature = "wifi")]
+    pub fn http_endpoint(&self) -> Option<&'static str> {
+        match self {
+            GPC::ShutterStart => Some("/gopro/camera/shutter/start"),
+            GPC::ShutterStop => Some("/gopro/camera/shutter/stop"),
+            GPC::AddHilightDuringEncoding => Some("/gopro/media/hilight/moment"),
+            GPC::VideoMode => Some("/gopro/camera/presets/set_group?id=1000"),
+            GPC::PhotoMode => Some("/gopro/camera/presets/set_group?id=1001"),
+            GPC::TimelapseMode => Some("/gopro/camera/presets/set_group?id=1002"),
+            GPC::Sleep | GPC::WifiApOn | GPC::WifiApOff => None,
+        }
+    }
+}
+
 ///Implement Sendable for all GoProCommands generically
 ///to avoid the duplicate code of also implementing it
 ///for references to GoProCommands
@@ -43,6 +72,8 @@ impl Sendable for GPC {
             GPC::VideoMode => &[0x04, 0x3E, 0x02, 0x03, 0xE8],
             GPC::PhotoMode => &[0x04, 0x3E, 0x02, 0x03, 0xE9],
             GPC::TimelapseMode => &[0x04, 0x3E, 0x02, 0x03, 0xEA],
+            GPC::WifiApOn => &[0x03, 0x17, 0x01, 0x01],
+            GPC::WifiApOff => &[0x03, 0x17, 0x01, 0x00],
         }
     }
     fn response_value_bytes(&self) -> &'static [u8] {
@@ -54,6 +85,8 @@ impl Sendable for GPC {
             GPC::VideoMode => &[0x02, 0x3E, 0x00],
             GPC::PhotoMode => &[0x02, 0x3E, 0x00],
             GPC::TimelapseMode => &[0x02, 0x3E, 0x00],
+            GPC::WifiApOn => &[0x02, 0x17, 0x00],
+            GPC::WifiApOff => &[0x02, 0x17, 0x00],
         }
     }
 }
@@ -0,0 +1,233 @@
+use crate::http::GoProHttp;
+use crate::query::{GoProQuery, QueryResponse, QueryResponseIntepretation as QRI, StatusID};
+#[cfg(feature = "settings")]
+use crate::settings::GoProSetting;
+use crate::snapshot::CameraSnapshot;
+use crate::{GoPro, GoProCommand};
+use futures::future::LocalBoxFuture;
+use futures::stream::{self, LocalBoxStream, StreamExt};
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::Duration;
+
+///Something that can control a GoPro, whichever transport it uses
+///
+///Implemented for [`GoPro`] (BLE) and [`GoProHttp`] (WiFi), so that code written
+///against this trait can drive a camera over either
+pub trait Controller {
+    ///Sends a command to the camera
+    fn send_command<'a>(
+        &'a self,
+        command: &'a GoProCommand,
+    ) -> LocalBoxFuture<'a, Result<(), Box<dyn Error>>>;
+
+    #[cfg(feature = "settings")]
+    ///Changes a setting on the camera
+    fn send_setting<'a>(
+        &'a self,
+        setting: &'a GoProSetting,
+    ) -> LocalBoxFuture<'a, Result<(), Box<dyn Error>>>;
+
+    ///Returns the value of every status the camera reports that this crate knows
+    fn statuses(&self) -> LocalBoxFuture<'_, Result<HashMap<StatusID, QRI>, Box<dyn Error>>>;
+
+    ///Returns the option ID of every setting the camera reports, keyed by setting ID
+    fn settings(&self) -> LocalBoxFuture<'_, Result<HashMap<u8, u32>, Box<dyn Error>>>;
+
+    ///Returns the statuses and settings of the camera, which are the same
+    ///whichever transport fetched them
+    fn snapshot(&self) -> LocalBoxFuture<'_, Result<CameraSnapshot, Box<dyn Error>>> {
+        Box::pin(async move {
+            let statuses = self.statuses().await?;
+            let settings = self.settings().await?;
+            Ok(CameraSnapshot::new(statuses, settings))
+        })
+    }
+
+    ///Returns a stream of the statuses of the camera, fetched immediately and then
+    ///every `interval` for as long as the stream is polled
+    fn poll_statuses(
+        &self,
+        interval: Duration,
+    ) -> LocalBoxStream<'_, Result<HashMap<StatusID, QRI>, Box<dyn Error>>> {
+        stream::unfold(true, move |first| async move {
+            if !first {
+                tokio::time::sleep(interval).await;
+            }
+            Some((self.statuses().await, false))
+        })
+        .boxed_local()
+    }
+}
+
+impl Controller for GoPro {
+    fn send_command<'a>(
+        &'a self,
+        command: &'a GoProCommand,
+    ) -> LocalBoxFuture<'a, Result<(), Box<dyn Error>>> {
+        Box::pin(GoPro::send_command(self, command))
+    }
+
+    #[cfg(feature = "settings")]
+    fn send_setting<'a>(
+        &'a self,
+        setting: &'a GoProSetting,
+    ) -> LocalBoxFuture<'a, Result<(), Box<dyn Error>>> {
+        Box::pin(GoPro::send_setting(self, setting))
+    }
+
+    fn statuses(&self) -> LocalBoxFuture<'_, Result<HashMap<StatusID, QRI>, Box<dyn Error>>> {
+        Box::pin(async move {
+            let responses = self.query_all(&GoProQuery::GetAllStatusValues).await?;
+            Ok(statuses(&responses))
+        })
+    }
+
+    fn settings(&self) -> LocalBoxFuture<'_, Result<HashMap<u8, u32>, Box<dyn Error>>> {
+        Box::pin(async move {
+            let responses = self.query_all(&GoProQuery::GetAllSettingValues).await?;
+            Ok(setting_options(&responses))
+        })
+    }
+}
+
+impl Controller for GoProHttp {
+    fn send_command<'a>(
+        &'a self,
+        command: &'a GoProCommand,
+    ) -> LocalBoxFuture<'a, Result<(), Box<dyn Error>>> {
+        Box::pin(GoProHttp::send_command(self, command))
+    }
+
+    #[cfg(feature = "settings")]
+    fn send_setting<'a>(
+        &'a self,
+        setting: &'a GoProSetting,
+    ) -> LocalBoxFuture<'a, Result<(), Box<dyn Error>>> {
+        Box::pin(self.set_setting(setting))
+    }
+
+    fn statuses(&self) -> LocalBoxFuture<'_, Result<HashMap<StatusID, QRI>, Box<dyn Error>>> {
+        Box::pin(GoProHttp::statuses(self))
+    }
+
+    fn settings(&self) -> LocalBoxFuture<'_, Result<HashMap<u8, u32>, Box<dyn Error>>> {
+        Box::pin(GoProHttp::settings(self))
+    }
+
+    //Both come from the same request over HTTP
+    fn snapshot(&self) -> LocalBoxFuture<'_, Result<CameraSnapshot, Box<dyn Error>>> {
+        Box::pin(GoProHttp::snapshot(self))
+    }
+}
+
+///Interprets the responses to a status query
+fn statuses(responses: &[QueryResponse]) -> HashMap<StatusID, QRI> {
+    responses
+        .iter()
+        .filter_map(|r| Some((StatusID::try_from(r.status_id).ok()?, r.interpret()?)))
+        .collect()
+}
+
+///Reads the option IDs from the responses to a setting query, keyed by setting ID
+fn setting_options(responses: &[QueryResponse]) -> HashMap<u8, u32> {
+    responses
+        .iter()
+        .filter_map(|r| Some((r.status_id, option_id(r)?)))
+        .collect()
+}
+
+///Reads the option ID held by the response to a setting query, where the
+///status ID of the response is the setting ID
+fn option_id(response: &QueryResponse) -> Option<u32> {
+    if response.status_value.is_empty() || response.status_value.len() > 4 {
+        return None;
+    }
+    Some(
+        response
+            .status_value
+            .iter()
+            .fold(0u32, |value, byte| (value << 8) | *byte as u32),
+    )
+}
+
+#[cfg(test)]
+use crate::mock_http::{self, Response};
+
+///Code written against the trait, as an automation library would be
+#[cfg(test)]
+async fn start_photo(camera: &impl Controller) -> Result<bool, Box<dyn Error>> {
+    camera.send_command(&GoProCommand::PhotoMode).await?;
+    let statuses = camera.statuses().await?;
+    Ok(statuses.get(&StatusID::SystemBusy) == Some(&QRI::Bool(false)))
+}
+
+#[tokio::test]
+async fn test_http_controller() {
+    let (base_url, log) = mock_http::serve(|request| match request.path.as_str() {
+        "/gopro/camera/state" => Response::ok(r#"{"status": {"8": 0}, "settings": {"2": 1}}"#),
+        _ => Response::ok("{}"),
+    })
+    .await;
+    let camera = GoProHttp::with_base_url(base_url).unwrap();
+    assert!(start_photo(&camera).await.unwrap());
+
+    let mut polls = camera.poll_statuses(Duration::from_millis(10));
+    for _ in 0..2 {
+        let statuses = polls.next().await.unwrap().unwrap();
+        assert_eq!(statuses[&StatusID::SystemBusy], QRI::Bool(false));
+    }
+    drop(polls);
+    assert_eq!(Controller::settings(&camera).await.unwrap()[&2], 1);
+
+    let paths: Vec<String> = log.lock().unwrap().iter().map(|r| r.path.clone()).collect();
+    assert_eq!(paths[0], "/gopro/camera/presets/set_group?id=1001");
+    assert_eq!(paths.len(), 5);
+}
+
+#[test]
+fn test_option_id() {
+    let responses =
+        QueryResponse::deserialize_many(&[0x12, 0x00, 0x02, 0x01, 0x64, 0x03, 0x00]).unwrap();
+    assert_eq!(option_id(&responses[0]), Some(100));
+    assert_eq!(option_id(&responses[1]), None);
+}
+
+#[cfg(feature = "settings")]
+#[test]
+fn test_snapshots_match() {
+    use crate::settings::{Hero11FPS, Hero11Resolution};
+
+    // The same camera state as sent over BLE and over HTTP
+    let ble_statuses = QueryResponse::deserialize_many(&[
+        0x13, 0x00, 0x08, 0x01, 0x00, 0x0D, 0x04, 0x00, 0x00, 0x00, 0x2A, 0x46, 0x01, 0x57, 0x1E,
+        0x04, b'G', b'P', b'2', b'4', 0x59, 0x01, 0x0C,
+    ])
+    .unwrap();
+    let ble_settings =
+        QueryResponse::deserialize_many(&[0x12, 0x00, 0x02, 0x01, 0x64, 0x03, 0x01, 0x01]).unwrap();
+    let ble = CameraSnapshot::new(statuses(&ble_statuses), setting_options(&ble_settings));
+
+    let state: serde_json::Value = serde_json::from_str(
+        r#"{"status": {"8": 0, "13": 42, "70": 87, "30": "GP24", "89": 12},
+            "settings": {"2": 100, "3": 1}}"#,
+    )
+    .unwrap();
+    let http = CameraSnapshot::new(
+        crate::http::parse_statuses(&state),
+        crate::http::parse_settings(&state),
+    );
+
+    assert_eq!(ble, http);
+    assert_eq!(
+        http.status(StatusID::CurrentMode).unwrap().as_integer(),
+        Some(12)
+    );
+    assert_eq!(
+        http.settings(),
+        vec![
+            GoProSetting::Resolution(Hero11Resolution::Res5_3K),
+            GoProSetting::Fps(Hero11FPS::Fps120)
+        ]
+    );
+}
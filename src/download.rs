@@ -0,0 +1,399 @@
+//Resumable downloads of media from the camera over HTTP
+
+use crate::http::GoProHttp;
+use crate::media::MediaFile;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+///How many times a download is attempted before giving up, resuming from
+///where the last attempt stopped each time
+const MAX_DOWNLOAD_ATTEMPTS: u32 = 5;
+
+///How far along a download is, as passed to the progress callback of [`GoProHttp::download`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadProgress {
+    ///The number of bytes on disk so far, including any resumed from an earlier download
+    pub downloaded: u64,
+    ///The size of the file, once it is known
+    pub total: Option<u64>,
+    ///The average rate of the download so far, not counting resumed bytes
+    pub bytes_per_second: f64,
+    ///The estimated time left, once the size and rate are known
+    pub eta: Option<Duration>,
+}
+
+///Returns where the partially downloaded file is kept until it is complete
+fn partial_path(destination: &Path) -> PathBuf {
+    let mut name = destination.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+///Reads the start and total size from a `Content-Range: bytes start-end/total` header
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.parse().ok()?, total.parse().ok()))
+}
+
+///Whether an error is the camera refusing the request, e.g. because the file doesn't
+///exist, which trying again won't fix
+fn is_refusal(error: &(dyn Error + 'static)) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.status().is_some())
+}
+
+///The state of a download that is carried between attempts
+struct Transfer<'a, F> {
+    partial: PathBuf,
+    downloaded: u64,
+    total: Option<u64>,
+    resumed_from: u64,
+    started: Instant,
+    progress: &'a mut F,
+}
+
+impl<F: FnMut(&DownloadProgress)> Transfer<'_, F> {
+    fn report(&mut self) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let bytes_per_second = match elapsed > 0.0 {
+            true => (self.downloaded - self.resumed_from) as f64 / elapsed,
+            false => 0.0,
+        };
+        let eta = match (self.total, bytes_per_second > 0.0) {
+            (Some(total), true) => Some(Duration::from_secs_f64(
+                total.saturating_sub(self.downloaded) as f64 / bytes_per_second,
+            )),
+            _ => None,
+        };
+        (self.progress)(&DownloadProgress {
+            downloaded: self.downloaded,
+            total: self.total,
+            bytes_per_second,
+            eta,
+        });
+    }
+
+    ///Requests the rest of the file and appends it to the partial file until the
+    ///response ends or the connection drops
+    async fn attempt(&mut self, http: &GoProHttp, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut request = http.request(path);
+        if self.downloaded > 0 {
+            request = request.header(RANGE, format!("bytes={}-", self.downloaded));
+        }
+        let mut response = request.send().await?;
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && self.downloaded > 0 {
+            //The partial file is as long as the file or longer, which happens when the size
+            //isn't known up front, so it is thrown away and the file downloaded again
+            fs::remove_file(&self.partial).await?;
+            self.downloaded = 0;
+            self.resumed_from = 0;
+            response = http.request(path).send().await?;
+        }
+        let mut response = response.error_for_status()?;
+
+        let mut out = match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                let range = response
+                    .headers()
+                    .get(CONTENT_RANGE)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(parse_content_range)
+                    .ok_or("Invalid Content-Range in partial response")?;
+                if range.0 != self.downloaded {
+                    return Err("The camera resumed the download at the wrong offset".into());
+                }
+                self.total = range.1.or(self.total);
+                OpenOptions::new().append(true).open(&self.partial).await?
+            }
+            //The camera ignored the range, so start again from the beginning
+            _ => {
+                self.downloaded = 0;
+                self.resumed_from = 0;
+                self.total = response.content_length().or(self.total);
+                fs::File::create(&self.partial).await?
+            }
+        };
+
+        //Chunks are written as they arrive so whole chapters are never held in memory
+        let result = async {
+            while let Some(chunk) = response.chunk().await? {
+                out.write_all(&chunk).await?;
+                self.downloaded += chunk.len() as u64;
+                self.report();
+            }
+            Ok::<(), Box<dyn Error>>(())
+        }
+        .await;
+        out.flush().await?;
+        result
+    }
+}
+
+///Downloads a file from the camera, resuming a previous download of it if one was interrupted
+///
+///See [`GoProHttp::download`]
+pub(crate) async fn download(
+    http: &GoProHttp,
+    file: &MediaFile,
+    destination: &Path,
+    mut progress: impl FnMut(&DownloadProgress),
+) -> Result<u64, Box<dyn Error>> {
+    let path = format!("/videos/DCIM/{}", file.path());
+    //The size in the media list is of the whole group for grouped media
+    let expected = file.group.is_none().then_some(file.size);
+
+    let partial = partial_path(destination);
+    let on_disk = match fs::metadata(&partial).await {
+        //A partial file larger than the file can't be resumed, so it is started again
+        Ok(metadata) if expected.is_none_or(|e| metadata.len() <= e) => metadata.len(),
+        _ => 0,
+    };
+    let mut transfer = Transfer {
+        partial,
+        downloaded: on_disk,
+        total: expected,
+        resumed_from: on_disk,
+        started: Instant::now(),
+        progress: &mut progress,
+    };
+
+    let mut attempts = 0;
+    while transfer.total != Some(transfer.downloaded) {
+        attempts += 1;
+        match transfer.attempt(http, &path).await {
+            //Without a known size the end of a response is the end of the file
+            Ok(()) if transfer.total.is_none() => break,
+            Ok(()) => {}
+            Err(e) if attempts >= MAX_DOWNLOAD_ATTEMPTS || is_refusal(e.as_ref()) => return Err(e),
+            Err(_) => {}
+        }
+        if transfer
+            .total
+            .is_some_and(|total| transfer.downloaded > total)
+        {
+            return Err(format!(
+                "Downloaded {} bytes of {}, more than the file holds",
+                transfer.downloaded,
+                transfer.total.unwrap_or_default()
+            )
+            .into());
+        }
+        if attempts >= MAX_DOWNLOAD_ATTEMPTS && transfer.total != Some(transfer.downloaded) {
+            return Err("The download was interrupted too many times".into());
+        }
+    }
+
+    if let Some(expected) = expected {
+        if transfer.downloaded != expected {
+            return Err(format!(
+                "Downloaded {} bytes but the media list says {} holds {}",
+                transfer.downloaded,
+                file.path(),
+                expected
+            )
+            .into());
+        }
+    }
+    fs::rename(&transfer.partial, destination).await?;
+    Ok(transfer.downloaded)
+}
+
+#[cfg(test)]
+use crate::mock_http::{self, Request, Response};
+
+///Serves `content` at `path` like the camera does, honouring ranges and
+///dropping the first connection after `drop_after` bytes
+#[cfg(test)]
+fn serve_file(
+    path: &'static str,
+    content: Vec<u8>,
+    drop_after: Option<usize>,
+) -> impl Fn(&Request) -> Response + Send + Sync + 'static {
+    let dropped = std::sync::atomic::AtomicBool::new(false);
+    move |request| {
+        if request.path != path {
+            return Response::status(404);
+        }
+        let start: usize = request
+            .header("range")
+            .and_then(|r| r.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok())
+            .unwrap_or(0);
+        let mut response = match start {
+            0 => Response::ok(content.clone()),
+            start if start >= content.len() => return Response::status(416),
+            start => Response {
+                status: 206,
+                ..Response::ok(content[start..].to_vec())
+            }
+            .header(
+                "Content-Range",
+                format!("bytes {}-{}/{}", start, content.len() - 1, content.len()),
+            ),
+        };
+        if !dropped.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            response.truncate_after = drop_after;
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+fn test_file(name: &str, size: u64) -> MediaFile {
+    MediaFile {
+        directory: "100GOPRO".into(),
+        name: name.into(),
+        size,
+        created: 0,
+        modified: 0,
+        group: None,
+        lrv_size: None,
+    }
+}
+
+#[cfg(test)]
+fn temp_destination(name: &str) -> PathBuf {
+    let destination =
+        std::env::temp_dir().join(format!("gopro-download-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_file(&destination);
+    let _ = std::fs::remove_file(partial_path(&destination));
+    destination
+}
+
+#[tokio::test]
+async fn test_download_resumes_after_drop() {
+    let content: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+    let (base_url, log) = mock_http::serve(serve_file(
+        "/videos/DCIM/100GOPRO/GX010001.MP4",
+        content.clone(),
+        Some(30_000),
+    ))
+    .await;
+    let http = GoProHttp::with_base_url(base_url).unwrap();
+    let destination = temp_destination("GX010001.MP4");
+
+    let mut updates = Vec::new();
+    let size = http
+        .download(
+            &test_file("GX010001.MP4", content.len() as u64),
+            &destination,
+            |p| updates.push(p.clone()),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(size, content.len() as u64);
+    assert_eq!(std::fs::read(&destination).unwrap(), content);
+    assert!(!partial_path(&destination).exists());
+    let last = updates.last().unwrap();
+    assert_eq!(last.downloaded, content.len() as u64);
+    assert_eq!(last.total, Some(content.len() as u64));
+    assert_eq!(last.eta, Some(Duration::ZERO));
+    assert!(updates
+        .windows(2)
+        .all(|w| w[0].downloaded <= w[1].downloaded));
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].header("range"), None);
+    assert_eq!(log[1].header("range"), Some("bytes=30000-"));
+    std::fs::remove_file(&destination).unwrap();
+}
+
+#[tokio::test]
+async fn test_download_resumes_partial_file() {
+    let content: Vec<u8> = (0..10_000u32).map(|i| (i * 7) as u8).collect();
+    let (base_url, log) = mock_http::serve(serve_file(
+        "/videos/DCIM/100GOPRO/GOPR0002.JPG",
+        content.clone(),
+        None,
+    ))
+    .await;
+    let http = GoProHttp::with_base_url(base_url).unwrap();
+    let destination = temp_destination("GOPR0002.JPG");
+    std::fs::write(partial_path(&destination), &content[..4_000]).unwrap();
+
+    http.download(
+        &test_file("GOPR0002.JPG", content.len() as u64),
+        &destination,
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(std::fs::read(&destination).unwrap(), content);
+    assert_eq!(log.lock().unwrap()[0].header("range"), Some("bytes=4000-"));
+    std::fs::remove_file(&destination).unwrap();
+}
+
+#[tokio::test]
+async fn test_download_restarts_unsatisfiable_range() {
+    let content: Vec<u8> = (0..5_000u32).map(|i| (i * 3) as u8).collect();
+    let (base_url, log) = mock_http::serve(serve_file(
+        "/videos/DCIM/100GOPRO/G0010004.JPG",
+        content.clone(),
+        None,
+    ))
+    .await;
+    let http = GoProHttp::with_base_url(base_url).unwrap();
+    let destination = temp_destination("G0010004.JPG");
+    //A member of a burst, whose size isn't known, left with a partial file that is too long
+    std::fs::write(partial_path(&destination), vec![0; 6_000]).unwrap();
+    let file = MediaFile {
+        group: Some(crate::media::MediaGroup {
+            id: 1,
+            kind: crate::media::MediaGroupKind::Burst,
+            first: 3,
+            last: 4,
+            missing: Vec::new(),
+        }),
+        ..test_file("G0010004.JPG", 10_000)
+    };
+
+    let size = http.download(&file, &destination, |_| {}).await.unwrap();
+    assert_eq!(size, content.len() as u64);
+    assert_eq!(std::fs::read(&destination).unwrap(), content);
+    let ranges: Vec<Option<String>> = log
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|r| r.header("range").map(str::to_string))
+        .collect();
+    assert_eq!(ranges, vec![Some("bytes=6000-".to_string()), None]);
+    std::fs::remove_file(&destination).unwrap();
+}
+
+#[tokio::test]
+async fn test_download_size_mismatch() {
+    let (base_url, _) = mock_http::serve(serve_file(
+        "/videos/DCIM/100GOPRO/GOPR0003.JPG",
+        vec![1; 1_000],
+        None,
+    ))
+    .await;
+    let http = GoProHttp::with_base_url(base_url).unwrap();
+    let destination = temp_destination("GOPR0003.JPG");
+
+    let result = http
+        .download(&test_file("GOPR0003.JPG", 2_000), &destination, |_| {})
+        .await;
+    assert!(result.is_err());
+    assert!(!destination.exists());
+    let _ = std::fs::remove_file(partial_path(&destination));
+}
+
+#[test]
+fn test_parse_content_range() {
+    assert_eq!(
+        parse_content_range("bytes 100-199/200"),
+        Some((100, Some(200)))
+    );
+    assert_eq!(parse_content_range("bytes 100-199/*"), Some((100, None)));
+    assert_eq!(parse_content_range("items 1-2/3"), None);
+}
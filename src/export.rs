@@ -0,0 +1,268 @@
+//Export of GPMF telemetry to the formats mapping and analytics tools read
+//
+//GPS fixes become a GPX track or a GeoJSON line, and any other stream (e.g. the
+//accelerometer) becomes CSV with one row per sample, timed from the start of the video
+
+use crate::gpmf::{self, GpsSample, Telemetry};
+use std::error::Error;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+///Formats a time as ISO 8601 in UTC with milliseconds, e.g. `2023-10-06T12:34:56.000Z`
+fn format_utc(time: SystemTime) -> Option<String> {
+    let millis = time.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64;
+    let (days, millis) = (millis.div_euclid(86_400_000), millis.rem_euclid(86_400_000));
+    let (year, month, day) = gpmf::civil_from_days(days);
+    let seconds = millis / 1000;
+    Some(format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        millis % 1000
+    ))
+}
+
+impl Telemetry {
+    ///Returns the GPS fixes worth plotting, leaving out those taken without a fix
+    fn track(&self) -> impl Iterator<Item = &GpsSample> {
+        self.gps.iter().filter(|sample| {
+            sample.fix != Some(0)
+                && [sample.latitude, sample.longitude, sample.altitude]
+                    .iter()
+                    .all(|v| v.is_finite())
+        })
+    }
+
+    ///Returns the GPS fixes as a GPX 1.1 track
+    ///
+    ///Fixes the camera recorded without a GPS lock are left out. Points are timed by the
+    ///UTC time from the GPS when the recording has it
+    pub fn to_gpx(&self) -> String {
+        let mut gpx = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<gpx version=\"1.1\" creator=\"gopro-controller\" ",
+            "xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+            "  <trk>\n    <trkseg>\n"
+        ));
+        for sample in self.track() {
+            gpx += &format!(
+                "      <trkpt lat=\"{}\" lon=\"{}\">\n        <ele>{}</ele>\n",
+                sample.latitude, sample.longitude, sample.altitude
+            );
+            if let Some(time) = sample.utc.and_then(format_utc) {
+                gpx += &format!("        <time>{time}</time>\n");
+            }
+            match sample.fix {
+                Some(2) => gpx += "        <fix>2d</fix>\n",
+                Some(3) => gpx += "        <fix>3d</fix>\n",
+                _ => {}
+            }
+            if let Some(dop) = sample.dop.filter(|dop| dop.is_finite()) {
+                gpx += &format!("        <pdop>{dop}</pdop>\n");
+            }
+            gpx += "      </trkpt>\n";
+        }
+        gpx + "    </trkseg>\n  </trk>\n</gpx>\n"
+    }
+
+    ///Returns the GPS fixes as a GeoJSON feature collection holding one line string
+    ///
+    ///Coordinates are longitude, latitude and altitude. The properties of the line hold
+    ///the video time of each point in seconds (`times`), its ground speed in m/s
+    ///(`speeds`, null where the camera didn't record one) and, when the recording has
+    ///it, its UTC time (`coordTimes`)
+    pub fn to_geojson(&self) -> String {
+        let track: Vec<&GpsSample> = self.track().collect();
+        let join = |values: Vec<String>| values.join(",");
+        let coordinates = join(
+            track
+                .iter()
+                .map(|s| format!("[{},{},{}]", s.longitude, s.latitude, s.altitude))
+                .collect(),
+        );
+        let times = join(
+            track
+                .iter()
+                .map(|s| format!("{:.6}", s.time.as_secs_f64()))
+                .collect(),
+        );
+        let speeds = join(
+            track
+                .iter()
+                .map(|s| match s.speed_2d.is_finite() {
+                    true => s.speed_2d.to_string(),
+                    false => "null".into(),
+                })
+                .collect(),
+        );
+        let mut properties = format!("\"times\":[{times}],\"speeds\":[{speeds}]");
+        let utc: Option<Vec<String>> = track
+            .iter()
+            .map(|s| s.utc.and_then(format_utc).map(|t| format!("\"{t}\"")))
+            .collect();
+        if let Some(utc) = utc.filter(|utc| !utc.is_empty()) {
+            properties += &format!(",\"coordTimes\":[{}]", join(utc));
+        }
+        format!(
+            concat!(
+                "{{\"type\":\"FeatureCollection\",\"features\":[{{\"type\":\"Feature\",",
+                "\"geometry\":{{\"type\":\"LineString\",\"coordinates\":[{}]}},",
+                "\"properties\":{{{}}}}}]}}\n"
+            ),
+            coordinates, properties
+        )
+    }
+
+    ///Returns the samples of a stream as CSV, one row per sample
+    ///
+    ///The first column is the time of the sample in seconds from the start of the video,
+    ///followed by a column for each value, named after the key and units of the stream
+    ///
+    /// # Arguments
+    /// * `key` - The key of the stream, e.g. `ACCL` or `GYRO`
+    pub fn to_csv(&self, key: &str) -> Result<String, Box<dyn Error>> {
+        let stream = self
+            .stream(key)
+            .ok_or_else(|| format!("The telemetry has no {key} stream"))?;
+        let columns = stream
+            .samples
+            .iter()
+            .map(|s| s.values.len())
+            .max()
+            .unwrap_or(0);
+        let mut csv = String::from("time (s)");
+        for index in 0..columns {
+            let unit = match stream.units.as_slice() {
+                [unit] => Some(unit),
+                units => units.get(index),
+            };
+            csv += &match unit.filter(|unit| !unit.is_empty()) {
+                Some(unit) => format!(",{key} {} ({unit})", index + 1),
+                None => format!(",{key} {}", index + 1),
+            };
+        }
+        csv += "\n";
+        for sample in &stream.samples {
+            csv += &format!("{:.6}", sample.time.as_secs_f64());
+            for value in &sample.values {
+                csv += &format!(",{value}");
+            }
+            csv += "\n";
+        }
+        Ok(csv)
+    }
+}
+
+///Writes the GPS track of a GoPro MP4 to a GPX file, see [`Telemetry::to_gpx`]
+///
+/// # Arguments
+/// * `input` - A downloaded MP4, either a chapter or joined chapters
+/// * `output` - Where to write the GPX
+pub fn export_gpx(input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+    std::fs::write(output, gpmf::read_telemetry(input)?.to_gpx())?;
+    Ok(())
+}
+
+///Writes the GPS track of a GoPro MP4 to a GeoJSON file, see [`Telemetry::to_geojson`]
+///
+/// # Arguments
+/// * `input` - A downloaded MP4, either a chapter or joined chapters
+/// * `output` - Where to write the GeoJSON
+pub fn export_geojson(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(output, gpmf::read_telemetry(input)?.to_geojson())?;
+    Ok(())
+}
+
+///Writes a telemetry stream of a GoPro MP4 to a CSV file, see [`Telemetry::to_csv`]
+///
+/// # Arguments
+/// * `input` - A downloaded MP4, either a chapter or joined chapters
+/// * `output` - Where to write the CSV
+/// * `key` - The key of the stream, e.g. `ACCL` or `GYRO`
+pub fn export_csv(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    key: &str,
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(output, gpmf::read_telemetry(input)?.to_csv(key)?)?;
+    Ok(())
+}
+
+#[test]
+fn test_format_utc() {
+    let time = UNIX_EPOCH + std::time::Duration::from_millis(1696595696500);
+    assert_eq!(format_utc(time).unwrap(), "2023-10-06T12:34:56.500Z");
+    assert_eq!(format_utc(UNIX_EPOCH).unwrap(), "1970-01-01T00:00:00.000Z");
+    let leap_day = UNIX_EPOCH + std::time::Duration::from_secs(951782400);
+    assert_eq!(format_utc(leap_day).unwrap(), "2000-02-29T00:00:00.000Z");
+}
+
+#[test]
+fn test_export_telemetry() {
+    let input = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/GX010001.MP4");
+    let telemetry = gpmf::read_telemetry(input).unwrap();
+
+    let gpx = telemetry.to_gpx();
+    assert!(gpx.contains(concat!(
+        "      <trkpt lat=\"47.5012345\" lon=\"-122.456789\">\n",
+        "        <ele>12.345</ele>\n",
+        "        <time>2023-10-06T12:34:56.000Z</time>\n",
+        "        <fix>3d</fix>\n",
+        "        <pdop>1.5</pdop>\n",
+        "      </trkpt>\n"
+    )));
+    assert!(gpx.contains("<time>2023-10-06T12:34:56.500Z</time>"));
+    assert_eq!(gpx.matches("<trkpt ").count(), 2);
+
+    let geojson = telemetry.to_geojson();
+    assert!(geojson.contains("\"coordinates\":[[-122.456789,47.5012345,12.345],[-122.45678,"));
+    assert!(geojson.contains("\"times\":[0.000000,0.500000],\"speeds\":[1.5,1.51]"));
+    assert!(geojson
+        .contains("\"coordTimes\":[\"2023-10-06T12:34:56.000Z\",\"2023-10-06T12:34:56.500Z\"]"));
+
+    let csv = telemetry.to_csv("ACCL").unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "time (s),ACCL 1 (m/s2),ACCL 2 (m/s2),ACCL 3 (m/s2)"
+    );
+    assert_eq!(lines[1], "0.000000,10,-1,2");
+    assert_eq!(lines[2], "0.333333,10,0,0");
+    assert_eq!(lines.len(), 4);
+    assert!(telemetry.to_csv("MAGN").is_err());
+
+    let output = std::env::temp_dir().join(format!("gopro-export-{}.csv", std::process::id()));
+    export_csv(input, &output, "GYRO").unwrap();
+    let csv = std::fs::read_to_string(&output).unwrap();
+    assert!(csv.starts_with("time (s),GYRO 1 (rad/s),"));
+    std::fs::remove_file(&output).unwrap();
+    assert!(export_gpx("missing.MP4", &output).is_err());
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_export_missing_values() {
+    //A fix whose speed and precision were filled in as NaN from a short sample
+    let telemetry = Telemetry {
+        gps: vec![GpsSample {
+            time: std::time::Duration::ZERO,
+            latitude: 47.5,
+            longitude: -122.5,
+            altitude: 12.0,
+            speed_2d: f64::NAN,
+            speed_3d: f64::NAN,
+            fix: Some(3),
+            dop: Some(f64::NAN),
+            utc: None,
+        }],
+        ..Default::default()
+    };
+    let gpx = telemetry.to_gpx();
+    assert!(gpx.contains("<fix>3d</fix>") && !gpx.contains("pdop") && !gpx.contains("NaN"));
+    let geojson = telemetry.to_geojson();
+    assert!(geojson.contains("\"speeds\":[null]") && !geojson.contains("NaN"));
+}
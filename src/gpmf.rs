@@ -0,0 +1,599 @@
+//Parser for GPMF, the telemetry GoPro records alongside video
+//
+//The format is described at <https://github.com/gopro/gpmf-parser>: a tree of
+//key-length-value entries, where each STRM (stream) in a DEVC (device) holds sticky
+//metadata such as the scale of its values followed by the samples themselves
+
+use crate::mp4::{self, Mp4, SampleTable};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+///The keys of sticky metadata in a stream, rather than of its samples
+const METADATA_KEYS: [&[u8; 4]; 13] = [
+    b"STMP", b"TSMP", b"STNM", b"SIUN", b"UNIT", b"SCAL", b"TYPE", b"ORIN", b"ORIO", b"MTRX",
+    b"GPSF", b"GPSP", b"GPSU",
+];
+
+///A sample of a stream, with its scale applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpmfSample {
+    ///When the sample was taken, from the start of the recording
+    pub time: Duration,
+    ///The values of the sample in the order the camera records them, e.g. the three
+    ///axes of an accelerometer (in the order its ORIN says, ZXY on recent cameras)
+    pub values: Vec<f64>,
+}
+
+///Every sample of one kind of telemetry, e.g. the accelerometer
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpmfStream {
+    ///The key of the samples, e.g. `ACCL`
+    pub key: String,
+    ///The name the camera gives the stream, e.g. `Accelerometer`
+    pub name: Option<String>,
+    ///The units of the values, one for all of them or one for each
+    pub units: Vec<String>,
+    pub samples: Vec<GpmfSample>,
+}
+
+///A GPS fix, from a GPS5 or GPS9 stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpsSample {
+    ///When the sample was taken, from the start of the recording
+    pub time: Duration,
+    ///Degrees north
+    pub latitude: f64,
+    ///Degrees east
+    pub longitude: f64,
+    ///Meters above the WGS84 ellipsoid
+    pub altitude: f64,
+    ///Ground speed in m/s
+    pub speed_2d: f64,
+    ///Speed including climbing or descending in m/s
+    pub speed_3d: f64,
+    ///0 without a fix, 2 for a 2D fix and 3 for a 3D fix
+    pub fix: Option<u32>,
+    ///The dilution of precision, under 5 being a good fix
+    pub dop: Option<f64>,
+    ///The UTC time of the sample, according to the GPS
+    pub utc: Option<SystemTime>,
+}
+
+///The telemetry of a recording
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Telemetry {
+    ///Every stream the recording holds, each over all of its payloads
+    pub streams: Vec<GpmfStream>,
+    ///The GPS fixes from the GPS5 or GPS9 streams
+    pub gps: Vec<GpsSample>,
+}
+
+impl Telemetry {
+    ///Returns the stream with a key, e.g. `ACCL`
+    pub fn stream(&self, key: &str) -> Option<&GpmfStream> {
+        self.streams.iter().find(|s| s.key == key)
+    }
+
+    ///Returns the samples of a stream, or none if the recording doesn't have it
+    fn samples(&self, key: &str) -> &[GpmfSample] {
+        self.stream(key).map_or(&[], |s| &s.samples)
+    }
+
+    ///Returns the accelerometer samples, in m/s²
+    pub fn accelerometer(&self) -> &[GpmfSample] {
+        self.samples("ACCL")
+    }
+
+    ///Returns the gyroscope samples, in rad/s
+    pub fn gyroscope(&self) -> &[GpmfSample] {
+        self.samples("GYRO")
+    }
+
+    ///Returns the temperature of the camera in °C
+    pub fn temperature(&self) -> &[GpmfSample] {
+        self.samples("TMPC")
+    }
+
+    ///Adds the telemetry of a later payload or recording
+    pub fn append(&mut self, other: Telemetry) {
+        for stream in other.streams {
+            match self.streams.iter_mut().find(|s| s.key == stream.key) {
+                Some(existing) => existing.samples.extend(stream.samples),
+                None => self.streams.push(stream),
+            }
+        }
+        self.gps.extend(other.gps);
+    }
+}
+
+///A key-length-value entry
+struct Klv<'a> {
+    key: [u8; 4],
+    kind: u8,
+    ///The size of one sample
+    size: usize,
+    repeat: usize,
+    data: &'a [u8],
+}
+
+///Parses the entries laid out one after another in `data`
+fn parse_klvs(mut data: &[u8]) -> Result<Vec<Klv<'_>>, Box<dyn Error>> {
+    let mut klvs = Vec::new();
+    while data.len() >= 8 {
+        let key: [u8; 4] = data[..4].try_into()?;
+        let (kind, size) = (data[4], data[5] as usize);
+        let repeat = u16::from_be_bytes([data[6], data[7]]) as usize;
+        let len = size * repeat;
+        let padded = len.div_ceil(4) * 4;
+        if data.len() < 8 + len {
+            return Err(format!("Truncated {} entry", String::from_utf8_lossy(&key)).into());
+        }
+        klvs.push(Klv {
+            key,
+            kind,
+            size,
+            repeat,
+            data: &data[8..8 + len],
+        });
+        data = &data[(8 + padded).min(data.len())..];
+    }
+    Ok(klvs)
+}
+
+///Returns the size of a value of a type, or None for nested entries and unknown types
+fn type_size(kind: u8) -> Option<usize> {
+    match kind {
+        b'b' | b'B' | b'c' => Some(1),
+        b's' | b'S' => Some(2),
+        b'l' | b'L' | b'f' | b'q' | b'F' => Some(4),
+        b'j' | b'J' | b'd' | b'Q' => Some(8),
+        b'G' | b'U' => Some(16),
+        _ => None,
+    }
+}
+
+///Reads a number of a type, or None for text and other values that aren't numbers
+fn number(kind: u8, bytes: &[u8]) -> Option<f64> {
+    let bytes4 = || bytes.try_into().ok();
+    let bytes8 = || bytes.try_into().ok();
+    Some(match kind {
+        b'b' => *bytes.first()? as i8 as f64,
+        b'B' => *bytes.first()? as f64,
+        b's' => i16::from_be_bytes(bytes.try_into().ok()?) as f64,
+        b'S' => u16::from_be_bytes(bytes.try_into().ok()?) as f64,
+        b'l' => i32::from_be_bytes(bytes4()?) as f64,
+        b'L' => u32::from_be_bytes(bytes4()?) as f64,
+        b'f' => f32::from_be_bytes(bytes4()?) as f64,
+        b'q' => i32::from_be_bytes(bytes4()?) as f64 / 65536.0,
+        b'j' => i64::from_be_bytes(bytes8()?) as f64,
+        b'J' => u64::from_be_bytes(bytes8()?) as f64,
+        b'd' => f64::from_be_bytes(bytes8()?),
+        b'Q' => i64::from_be_bytes(bytes8()?) as f64 / 4294967296.0,
+        _ => return None,
+    })
+}
+
+impl Klv<'_> {
+    ///Reads the entry as text, with strings of several samples joined
+    fn text(&self) -> String {
+        //Units such as m/s² use Latin-1
+        let text: String = self.data.iter().map(|b| *b as char).collect();
+        text.trim_end_matches('\0').to_string()
+    }
+
+    ///Reads the entry as one string per sample, or a single string if its samples are
+    ///single characters
+    fn texts(&self) -> Vec<String> {
+        if self.size <= 1 {
+            return vec![self.text()];
+        }
+        self.data
+            .chunks(self.size)
+            .map(|chunk| {
+                let text: String = chunk.iter().map(|b| *b as char).collect();
+                text.trim_end_matches('\0').to_string()
+            })
+            .collect()
+    }
+
+    ///Reads the samples of the entry as numbers
+    ///
+    /// # Arguments
+    /// * `types` - The type of each value for complex (`?`) entries, from the TYPE of the stream
+    fn samples(&self, types: &[u8]) -> Vec<Vec<f64>> {
+        let types: Vec<u8> = match self.kind {
+            b'?' => types.to_vec(),
+            kind => match type_size(kind) {
+                Some(size) => vec![kind; self.size / size],
+                None => return Vec::new(),
+            },
+        };
+        self.data
+            .chunks_exact(self.size.max(1))
+            .take(self.repeat)
+            .map(|sample| {
+                let mut values = Vec::with_capacity(types.len());
+                let mut position = 0;
+                for kind in &types {
+                    let size = type_size(*kind).unwrap_or(0);
+                    let bytes = sample.get(position..position + size).unwrap_or_default();
+                    values.push(number(*kind, bytes).unwrap_or(f64::NAN));
+                    position += size;
+                }
+                values
+            })
+            .collect()
+    }
+}
+
+///Applies the scale of a stream, which has one divisor for every value or one for each
+fn scale(values: &mut [f64], scales: &[f64]) {
+    for (index, value) in values.iter_mut().enumerate() {
+        let scale = match scales {
+            [scale] => *scale,
+            scales => scales.get(index).copied().unwrap_or(1.0),
+        };
+        if scale != 0.0 {
+            *value /= scale;
+        }
+    }
+}
+
+///Returns the number of days from 1970-01-01 to a date
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+///Returns the year, month and day of a number of days from 1970-01-01, the inverse of
+///[`days_from_civil`]
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = if days >= 0 { days } else { days - 146096 } / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+///Parses the UTC time in a GPSU entry, e.g. `231006123456.000` for 2023-10-06 12:34:56
+fn parse_gpsu(text: &str) -> Option<SystemTime> {
+    let field = |range: std::ops::Range<usize>| text.get(range)?.parse::<u32>().ok();
+    let days = days_from_civil(2000 + field(0..2)? as i64, field(2..4)?, field(4..6)?);
+    let seconds: f64 = text.get(10..)?.parse().ok()?;
+    let time = days as f64 * 86400.0
+        + field(6..8)? as f64 * 3600.0
+        + field(8..10)? as f64 * 60.0
+        + seconds;
+    Some(UNIX_EPOCH + Duration::try_from_secs_f64(time).ok()?)
+}
+
+///Parses a GPMF payload, such as a sample of the GPMF track of a GoPro MP4
+///
+///The samples of each stream are spread evenly over the time the payload covers
+///
+/// # Arguments
+/// * `payload` - The GPMF data
+/// * `start` - When the payload starts, from the start of the recording
+/// * `duration` - How long the payload covers
+pub fn parse_gpmf(
+    payload: &[u8],
+    start: Duration,
+    duration: Duration,
+) -> Result<Telemetry, Box<dyn Error>> {
+    let mut telemetry = Telemetry::default();
+    for device in parse_klvs(payload)?.iter().filter(|d| &d.key == b"DEVC") {
+        for stream in parse_klvs(device.data)?
+            .iter()
+            .filter(|s| &s.key == b"STRM")
+        {
+            let mut stream = parse_stream(stream, start, duration)?;
+            //Each IMU stream carries the same temperature, so one reading per payload is kept
+            if telemetry.stream("TMPC").is_some() {
+                stream.streams.retain(|s| s.key != "TMPC");
+            }
+            telemetry.append(stream);
+        }
+    }
+    Ok(telemetry)
+}
+
+///Parses the samples and sticky metadata of a STRM entry
+fn parse_stream(
+    stream: &Klv,
+    start: Duration,
+    duration: Duration,
+) -> Result<Telemetry, Box<dyn Error>> {
+    let klvs = parse_klvs(stream.data)?;
+    let find = |key: &[u8; 4]| klvs.iter().find(|k| &k.key == key);
+    let numbers = |key: &[u8; 4]| -> Vec<f64> {
+        find(key)
+            .map(|k| k.samples(&[]).concat())
+            .unwrap_or_default()
+    };
+    let scales = numbers(b"SCAL");
+    let types = find(b"TYPE").map(|k| k.data.to_vec()).unwrap_or_default();
+    let units = match find(b"SIUN").or(find(b"UNIT")) {
+        Some(units) => units.texts(),
+        None => Vec::new(),
+    };
+
+    let mut telemetry = Telemetry::default();
+    //The samples are the last entry, after the sticky metadata
+    let Some(data) = klvs.last().filter(|k| !METADATA_KEYS.contains(&&k.key)) else {
+        return Ok(telemetry);
+    };
+    let mut samples = data.samples(&types);
+    let count = samples.len().max(1) as u32;
+    let time = |index: usize| start.saturating_add(duration / count * index as u32);
+    for values in &mut samples {
+        scale(values, &scales);
+    }
+
+    let key = String::from_utf8_lossy(&data.key).into_owned();
+    if key == "GPS5" || key == "GPS9" {
+        let fix = numbers(b"GPSF").first().map(|f| *f as u32);
+        let dop = numbers(b"GPSP").first().map(|p| p / 100.0);
+        let utc = find(b"GPSU").and_then(|u| parse_gpsu(&u.text()));
+        for (index, values) in samples.iter().enumerate() {
+            let &[latitude, longitude, altitude, speed_2d, speed_3d, ..] = values.as_slice() else {
+                continue;
+            };
+            let mut sample = GpsSample {
+                time: time(index),
+                latitude,
+                longitude,
+                altitude,
+                speed_2d,
+                speed_3d,
+                fix,
+                dop,
+                utc: utc.and_then(|utc| utc.checked_add(time(index) - start)),
+            };
+            //GPS9 has the time, precision and fix of every sample
+            if let &[.., days, seconds, dop, fix] = values.as_slice() {
+                if key == "GPS9" {
+                    let days = days_from_civil(2000, 1, 1) as f64 + days;
+                    sample.utc = Duration::try_from_secs_f64(days * 86400.0 + seconds)
+                        .ok()
+                        .map(|time| UNIX_EPOCH + time);
+                    sample.dop = Some(dop);
+                    sample.fix = Some(fix as u32);
+                }
+            }
+            telemetry.gps.push(sample);
+        }
+    }
+
+    //Recent cameras record the temperature as metadata of the IMU streams
+    if let Some(temperature) = find(b"TMPC").filter(|k| k.key != data.key) {
+        telemetry.streams.push(GpmfStream {
+            key: "TMPC".into(),
+            name: Some("Camera temperature".into()),
+            units: vec!["°C".into()],
+            samples: vec![GpmfSample {
+                time: start,
+                values: temperature.samples(&[]).concat(),
+            }],
+        });
+    }
+
+    telemetry.streams.push(GpmfStream {
+        key,
+        name: find(b"STNM").map(Klv::text),
+        units,
+        samples: samples
+            .into_iter()
+            .enumerate()
+            .map(|(index, values)| GpmfSample {
+                time: time(index),
+                values,
+            })
+            .collect(),
+    });
+    Ok(telemetry)
+}
+
+///Reads the telemetry from the GPMF track of a GoPro MP4
+fn read_mp4<R: Read + Seek>(reader: &mut R) -> Result<Telemetry, Box<dyn Error>> {
+    let mp4 = Mp4::read(reader)?;
+    let trak = mp4
+        .traks()
+        .find(|t| mp4::sample_entry(t).is_ok_and(|e| &e == b"gpmd"))
+        .ok_or("The file has no GPMF track")?;
+    let timescale = mp4::timescale(trak.find(&[b"mdia", b"mdhd"])?)?.max(1) as f64;
+
+    let mut telemetry = Telemetry::default();
+    let seconds = |ticks: u64| {
+        Duration::try_from_secs_f64(ticks as f64 / timescale)
+            .map_err(|_| "A GPMF sample has an invalid time")
+    };
+    for sample in SampleTable::parse(trak, mp4.media_len())?.samples()? {
+        //Read without allocating the size up front, as it comes from the file
+        let mut payload = Vec::new();
+        reader.seek(SeekFrom::Start(sample.offset))?;
+        reader
+            .by_ref()
+            .take(sample.size as u64)
+            .read_to_end(&mut payload)?;
+        if payload.len() != sample.size as usize {
+            return Err("A GPMF sample is past the end of the file".into());
+        }
+        telemetry.append(parse_gpmf(
+            &payload,
+            seconds(sample.time)?,
+            seconds(sample.duration as u64)?,
+        )?);
+    }
+    Ok(telemetry)
+}
+
+///Reads the telemetry (GPS, accelerometer, gyroscope, temperature and more) that a
+///GoPro records in the GPMF track of its MP4s
+///
+/// # Arguments
+/// * `path` - A downloaded MP4, either a chapter or chapters joined by
+///   [`crate::concat_chapters`]
+pub fn read_telemetry(path: impl AsRef<Path>) -> Result<Telemetry, Box<dyn Error>> {
+    read_mp4(&mut BufReader::new(File::open(path)?))
+}
+
+#[test]
+fn test_read_telemetry() {
+    let telemetry = read_mp4(&mut std::io::Cursor::new(mp4::CHAPTERS[0])).unwrap();
+
+    let accelerometer = telemetry.stream("ACCL").unwrap();
+    assert_eq!(accelerometer.name.as_deref(), Some("Accelerometer"));
+    assert_eq!(accelerometer.units, vec!["m/s2"]);
+    assert_eq!(accelerometer.samples.len(), 3);
+    assert_eq!(accelerometer.samples[0].values, vec![10.0, -1.0, 2.0]);
+    assert_eq!(accelerometer.samples[1].time, Duration::from_secs(1) / 3);
+    assert_eq!(telemetry.gyroscope()[2].values, vec![2.0, 1.0, 0.0]);
+    assert_eq!(telemetry.temperature()[0].values, vec![45.5]);
+
+    let gps = &telemetry.gps;
+    assert_eq!(gps.len(), 2);
+    assert_eq!(gps[0].latitude, 47.5012345);
+    assert_eq!(gps[0].longitude, -122.456789);
+    assert_eq!(
+        (gps[0].altitude, gps[0].speed_2d, gps[0].speed_3d),
+        (12.345, 1.5, 1.6)
+    );
+    assert_eq!((gps[0].fix, gps[0].dop), (Some(3), Some(1.5)));
+    assert_eq!(
+        gps[0].utc,
+        Some(UNIX_EPOCH + Duration::from_secs(1696595696))
+    );
+    assert_eq!(gps[1].time, Duration::from_millis(500));
+}
+
+#[test]
+fn test_read_joined_telemetry() {
+    let mut inputs: Vec<_> = mp4::CHAPTERS
+        .iter()
+        .map(|c| std::io::Cursor::new(*c))
+        .collect();
+    let mut joined = Vec::new();
+    mp4::concat(&mut inputs, &mut joined).unwrap();
+
+    let telemetry = read_mp4(&mut std::io::Cursor::new(joined)).unwrap();
+    let accelerometer = telemetry.accelerometer();
+    assert_eq!(accelerometer.len(), 5);
+    assert_eq!(accelerometer[3].time, Duration::from_secs(1));
+    assert_eq!(accelerometer[4].values, vec![10.0, 0.0, 0.0]);
+    assert_eq!(telemetry.gps.len(), 3);
+    assert_eq!(telemetry.temperature().len(), 2);
+}
+
+#[test]
+fn test_parse_shared_temperature() {
+    //ACCL and GYRO in one payload, both carrying the temperature as metadata
+    let imu = |key: &[u8; 4]| {
+        [
+            klv(b"TMPC", b'f', 4, 1, &42.5f32.to_be_bytes()),
+            klv(key, b's', 6, 1, &[0, 1, 0, 2, 0, 3]),
+        ]
+        .concat()
+    };
+    let payload = [device(&imu(b"ACCL")), device(&imu(b"GYRO"))].concat();
+    let telemetry = parse_gpmf(&payload, Duration::from_secs(1), Duration::from_secs(1)).unwrap();
+    let temperature = telemetry.temperature();
+    assert_eq!(temperature.len(), 1);
+    assert_eq!(temperature[0].time, Duration::from_secs(1));
+    assert_eq!(temperature[0].values, vec![42.5]);
+    assert_eq!(telemetry.gyroscope().len(), 1);
+}
+
+///Builds a KLV entry, for tests
+#[cfg(test)]
+fn klv(key: &[u8; 4], kind: u8, size: u8, repeat: u16, data: &[u8]) -> Vec<u8> {
+    let mut out = key.to_vec();
+    out.extend([kind, size]);
+    out.extend(repeat.to_be_bytes());
+    out.extend(data);
+    out.resize(out.len().div_ceil(4) * 4, 0);
+    out
+}
+
+///Builds a payload holding one device with one stream, for tests
+#[cfg(test)]
+fn device(stream: &[u8]) -> Vec<u8> {
+    let strm = klv(b"STRM", 0, 1, stream.len() as u16, stream);
+    klv(b"DEVC", 0, 1, strm.len() as u16, &strm)
+}
+
+#[test]
+fn test_parse_gps9() {
+    let scales: Vec<u8> = [10000000i32, 10000000, 1000, 1000, 100, 1, 1000, 100, 1]
+        .iter()
+        .flat_map(|s| s.to_be_bytes())
+        .collect();
+    let mut sample: Vec<u8> = [475012345i32, -1224567890, 12345, 1500, 160, 8679, 45296500]
+        .iter()
+        .flat_map(|v| v.to_be_bytes())
+        .collect();
+    sample.extend(150u16.to_be_bytes());
+    sample.extend(3u16.to_be_bytes());
+    let stream = [
+        klv(b"SCAL", b'l', 4, 9, &scales),
+        klv(b"TYPE", b'c', 1, 9, b"lllllllSS"),
+        klv(b"GPS9", b'?', 32, 1, &sample),
+    ]
+    .concat();
+    let device = device(&stream);
+
+    let telemetry = parse_gpmf(&device, Duration::from_secs(2), Duration::from_secs(1)).unwrap();
+    let gps = &telemetry.gps[0];
+    assert_eq!(gps.time, Duration::from_secs(2));
+    assert_eq!(gps.latitude, 47.5012345);
+    assert_eq!((gps.fix, gps.dop), (Some(3), Some(1.5)));
+    assert_eq!(
+        gps.utc,
+        Some(UNIX_EPOCH + Duration::from_millis(1696595696500))
+    );
+    assert!(parse_gpmf(&device[..20], Duration::ZERO, Duration::ZERO).is_err());
+}
+
+#[test]
+fn test_parse_corrupt_gps() {
+    //A GPS9 sample shorter than its TYPE, with a date before 1970
+    let sample: Vec<u8> = [475012345i32, -1224567890, 12345, 1500, 160, -1000000, 0]
+        .iter()
+        .flat_map(|v| v.to_be_bytes())
+        .collect();
+    let stream = [
+        klv(b"TYPE", b'c', 1, 9, b"lllllllbb"),
+        klv(b"GPS9", b'?', 28, 1, &sample),
+    ]
+    .concat();
+    let telemetry = parse_gpmf(&device(&stream), Duration::ZERO, Duration::from_secs(1)).unwrap();
+    let gps = &telemetry.gps[0];
+    assert_eq!(gps.utc, None);
+    assert!(gps.dop.unwrap().is_nan());
+
+    //A GPSU whose seconds aren't a number
+    let stream = [
+        klv(b"GPSU", b'U', 16, 1, b"231006123456nan\0"),
+        klv(b"GPS5", b'l', 20, 1, &sample[..20]),
+    ]
+    .concat();
+    let telemetry = parse_gpmf(&device(&stream), Duration::ZERO, Duration::from_secs(1)).unwrap();
+    assert_eq!(telemetry.gps[0].utc, None);
+    assert_eq!(parse_gpsu("2310061234inf"), None);
+}
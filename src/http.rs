@@ -0,0 +1,749 @@
+//Client for the HTTP API the GoPro serves over WiFi
+//
+//The endpoints are described in the GoPro Open Spec:
+//<https://gopro.github.io/OpenGoPro/http>
+
+use crate::chapters::{self, Recording};
+use crate::download::{self, DownloadProgress};
+use crate::media::{parse_media_info, parse_media_list, MediaDirectory, MediaFile, MediaInfo};
+use crate::query::{QueryResponseIntepretation as QRI, StatusID, StatusKind};
+#[cfg(feature = "settings")]
+use crate::settings::GoProSetting;
+use crate::snapshot::CameraSnapshot;
+use crate::sync::{self, SyncOptions, SyncReport};
+use crate::wifi::CohnCredentials;
+use crate::GoProCommand;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+///The address the GoPro serves the HTTP API on when it is connected to over its own WiFi AP
+pub const DEFAULT_HTTP_BASE_URL: &str = "http://10.5.5.9:8080";
+
+///How long to wait for a connection to the GoPro to be established
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+///Information about the camera, as returned by [`GoProHttp::camera_info`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CameraInfo {
+    pub model_number: u32,
+    pub model_name: String,
+    pub firmware_version: String,
+    pub serial_number: String,
+    pub ap_mac_addr: String,
+    pub ap_ssid: String,
+}
+
+///How much is stored on the camera, as returned after deleting media
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageStatus {
+    ///The space left on the SD card in kilobytes
+    pub remaining_space_kb: u64,
+    pub total_videos: u64,
+    pub total_photos: u64,
+}
+
+///A client for controlling a GoPro over its HTTP API
+pub struct GoProHttp {
+    client: reqwest::Client,
+    base_url: String,
+    ///The username and password for basic authentication, which COHN requires
+    auth: Option<(String, String)>,
+}
+
+impl GoProHttp {
+    ///Creates a client for a GoPro whose WiFi AP the host is connected to
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Self::with_base_url(DEFAULT_HTTP_BASE_URL)
+    }
+
+    ///Creates a client for a GoPro reachable at another address
+    ///
+    /// # Arguments
+    /// * `base_url` - The scheme, host and port of the GoPro, e.g. `http://10.5.5.9:8080`
+    pub fn with_base_url(base_url: impl Into<String>) -> Result<Self, Box<dyn Error>> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()?;
+        Ok(GoProHttp {
+            client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            auth: None,
+        })
+    }
+
+    ///Creates a client for a GoPro on the home network (COHN), which is reached over
+    ///HTTPS with the GoPro's own certificate and basic authentication
+    ///
+    /// # Arguments
+    /// * `credentials` - The credentials returned by [`crate::GoPro::cohn_credentials`]
+    pub fn from_cohn(credentials: &CohnCredentials) -> Result<Self, Box<dyn Error>> {
+        let certificate = reqwest::Certificate::from_pem(credentials.certificate.as_bytes())?;
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .add_root_certificate(certificate)
+            .build()?;
+        Ok(GoProHttp {
+            client,
+            base_url: format!("https://{}", credentials.ip_address),
+            auth: Some((credentials.username.clone(), credentials.password.clone())),
+        })
+    }
+
+    ///Returns the scheme, host and port requests are sent to
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    ///Sends a GET request to an endpoint and returns the response if it was successful
+    ///
+    /// # Arguments
+    /// * `path` - The path of the endpoint, starting with a `/`
+    /// * `query` - The query parameters to send
+    pub(crate) async fn get(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<reqwest::Response, Box<dyn Error>> {
+        Ok(self
+            .request(path)
+            .query(query)
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    ///Builds a GET request to an endpoint, authenticated if the camera requires it
+    pub(crate) fn request(&self, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.get(format!("{}{}", self.base_url, path));
+        match &self.auth {
+            Some((username, password)) => request.basic_auth(username, Some(password)),
+            None => request,
+        }
+    }
+
+    ///Returns the raw JSON holding every status and setting of the camera
+    pub async fn camera_state(&self) -> Result<Value, Box<dyn Error>> {
+        Ok(self.get("/gopro/camera/state", &[]).await?.json().await?)
+    }
+
+    ///Returns the value of every status the camera reports, interpreted the same
+    ///way as the responses to BLE queries
+    pub async fn statuses(&self) -> Result<HashMap<StatusID, QRI>, Box<dyn Error>> {
+        Ok(parse_statuses(&self.camera_state().await?))
+    }
+
+    ///Returns the option ID of every setting the camera reports, keyed by setting ID
+    pub async fn settings(&self) -> Result<HashMap<u8, u32>, Box<dyn Error>> {
+        Ok(parse_settings(&self.camera_state().await?))
+    }
+
+    ///Returns the statuses and settings of the camera, the same as
+    ///[`crate::GoPro`] would have fetched over BLE
+    pub async fn snapshot(&self) -> Result<CameraSnapshot, Box<dyn Error>> {
+        let state = self.camera_state().await?;
+        Ok(CameraSnapshot::new(
+            parse_statuses(&state),
+            parse_settings(&state),
+        ))
+    }
+
+    ///Returns the model, firmware and identifiers of the camera
+    pub async fn camera_info(&self) -> Result<CameraInfo, Box<dyn Error>> {
+        Ok(self.get("/gopro/camera/info", &[]).await?.json().await?)
+    }
+
+    ///Tells the camera the client is still connected, which keeps it from
+    ///powering down. The spec recommends sending this every 3 seconds
+    pub async fn keep_alive(&self) -> Result<(), Box<dyn Error>> {
+        self.get("/gopro/camera/keep_alive", &[]).await?;
+        Ok(())
+    }
+
+    ///Sends a command to the camera, the same way [`crate::GoPro::send_command`] does over BLE
+    ///
+    /// # Errors
+    /// For the commands that can only be sent over BLE (see [`GoProCommand::http_endpoint`])
+    pub async fn send_command(&self, command: &GoProCommand) -> Result<(), Box<dyn Error>> {
+        let endpoint = command
+            .http_endpoint()
+            .ok_or("This command can only be sent over BLE")?;
+        self.get(endpoint, &[]).await?;
+        Ok(())
+    }
+
+    ///Starts or stops capturing with the active preset
+    ///
+    /// # Arguments
+    /// * `on` - True to start capturing, false to stop
+    pub async fn set_shutter(&self, on: bool) -> Result<(), Box<dyn Error>> {
+        let path = match on {
+            true => "/gopro/camera/shutter/start",
+            false => "/gopro/camera/shutter/stop",
+        };
+        self.get(path, &[]).await?;
+        Ok(())
+    }
+
+    ///Loads a preset by its ID
+    pub async fn load_preset(&self, id: u32) -> Result<(), Box<dyn Error>> {
+        self.get("/gopro/camera/presets/load", &[("id", id.to_string())])
+            .await?;
+        Ok(())
+    }
+
+    ///Sets a setting to an option by their IDs
+    ///
+    /// # Arguments
+    /// * `setting_id` - The ID of the setting
+    /// * `option_id` - The ID of the option to set it to
+    pub async fn set_setting_option(
+        &self,
+        setting_id: u8,
+        option_id: u8,
+    ) -> Result<(), Box<dyn Error>> {
+        self.get(
+            "/gopro/camera/setting",
+            &[
+                ("setting", setting_id.to_string()),
+                ("option", option_id.to_string()),
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[cfg(feature = "settings")]
+    ///Sets a setting, the same way [`crate::GoPro::send_setting`] does over BLE
+    pub async fn set_setting(&self, setting: &GoProSetting) -> Result<(), Box<dyn Error>> {
+        self.set_setting_option(setting.setting_id(), setting.option_id())
+            .await
+    }
+
+    ///Returns every directory on the camera's SD card and the media in it
+    pub async fn media_list(&self) -> Result<Vec<MediaDirectory>, Box<dyn Error>> {
+        let json = self.get("/gopro/media/list", &[]).await?.text().await?;
+        parse_media_list(&json)
+    }
+
+    ///Returns the thumbnail of a file as a JPEG
+    pub async fn thumbnail(&self, file: &MediaFile) -> Result<Vec<u8>, Box<dyn Error>> {
+        let response = self
+            .get("/gopro/media/thumbnail", &[("path", file.path())])
+            .await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    ///Returns the screennail of a file, a preview larger than its thumbnail, as a JPEG
+    pub async fn screennail(&self, file: &MediaFile) -> Result<Vec<u8>, Box<dyn Error>> {
+        let response = self
+            .get("/gopro/media/screennail", &[("path", file.path())])
+            .await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    ///Returns the resolution, frame rate, duration, hilights and more of a file
+    pub async fn media_info(&self, file: &MediaFile) -> Result<MediaInfo, Box<dyn Error>> {
+        let response = self
+            .get("/gopro/media/info", &[("path", file.path())])
+            .await?;
+        parse_media_info(&response.text().await?)
+    }
+
+    ///Returns the videos on the camera with their chapters grouped into recordings,
+    ///see [`crate::recordings`]
+    ///
+    ///Unlike the media list this includes the duration and hilights of every recording,
+    ///which takes a request per chapter. The camera only reports whole seconds of a
+    ///chapter's duration, so hilights in later chapters can be off by up to a second
+    ///each
+    pub async fn recordings(&self) -> Result<Vec<Recording>, Box<dyn Error>> {
+        let mut recordings = chapters::recordings(&self.media_list().await?);
+        for recording in &mut recordings {
+            let (mut duration, mut hilights) = (Duration::ZERO, Vec::new());
+            for chapter in &recording.chapters {
+                let info = self.media_info(chapter).await?;
+                hilights.extend(info.hilights.iter().map(|offset| duration + *offset));
+                duration += info.duration.unwrap_or_default();
+            }
+            recording.duration = Some(duration);
+            recording.hilights = Some(hilights);
+        }
+        Ok(recordings)
+    }
+
+    ///Deletes a file from the camera, or every file in its group for grouped media
+    ///
+    ///Unlike the public deletes this doesn't check whether the camera is encoding,
+    ///so callers deleting many files can check once with [`Self::ensure_not_encoding`]
+    pub(crate) async fn delete_media(&self, file: &MediaFile) -> Result<(), Box<dyn Error>> {
+        let path = match file.group {
+            Some(_) => "/gopro/media/delete/group",
+            None => "/gopro/media/delete/file",
+        };
+        self.get(path, &[("path", file.path())]).await?;
+        Ok(())
+    }
+
+    ///Returns an error if the camera is encoding, when media must not be deleted
+    pub(crate) async fn ensure_not_encoding(&self) -> Result<(), Box<dyn Error>> {
+        let statuses = self.statuses().await?;
+        match statuses.get(&StatusID::EncodingActive) {
+            Some(QRI::Bool(false)) => Ok(()),
+            Some(_) => Err("Refusing to delete media while the camera is encoding".into()),
+            None => Err("The camera didn't report whether it is encoding".into()),
+        }
+    }
+
+    ///Returns how much space is left on the SD card and how many videos and photos are on it
+    pub async fn storage_status(&self) -> Result<StorageStatus, Box<dyn Error>> {
+        let statuses = self.statuses().await?;
+        let status = |id: StatusID| {
+            statuses
+                .get(&id)
+                .and_then(QRI::as_integer)
+                .ok_or(format!("The camera didn't report {id:?}"))
+        };
+        Ok(StorageStatus {
+            remaining_space_kb: status(StatusID::RemainingSpace)?,
+            total_videos: status(StatusID::NumTotalVideos)?,
+            total_photos: status(StatusID::NumTotalPhotos)?,
+        })
+    }
+
+    ///Deletes a single file from the camera
+    ///
+    /// # Returns
+    /// The storage status of the camera after the delete, to confirm the space was reclaimed
+    ///
+    /// # Errors
+    /// If the camera is encoding
+    pub async fn delete_file(&self, file: &MediaFile) -> Result<StorageStatus, Box<dyn Error>> {
+        self.ensure_not_encoding().await?;
+        self.get("/gopro/media/delete/file", &[("path", file.path())])
+            .await?;
+        self.storage_status().await
+    }
+
+    ///Deletes every file in a group (a burst, time lapse, ...) from the camera
+    ///
+    /// # Returns
+    /// The storage status of the camera after the delete, to confirm the space was reclaimed
+    ///
+    /// # Errors
+    /// If the camera is encoding, or the file isn't grouped media
+    pub async fn delete_group(&self, file: &MediaFile) -> Result<StorageStatus, Box<dyn Error>> {
+        if file.group.is_none() {
+            return Err(format!("{} isn't grouped media", file.path()).into());
+        }
+        self.ensure_not_encoding().await?;
+        self.delete_media(file).await?;
+        self.storage_status().await
+    }
+
+    ///Deletes all media from the camera
+    ///
+    /// # Returns
+    /// The storage status of the camera after the delete, to confirm the space was reclaimed
+    ///
+    /// # Errors
+    /// If the camera is encoding, or any media fails to delete
+    pub async fn delete_all(&self) -> Result<StorageStatus, Box<dyn Error>> {
+        self.ensure_not_encoding().await?;
+        for directory in self.media_list().await? {
+            for file in &directory.files {
+                self.delete_media(file).await?;
+            }
+        }
+        self.storage_status().await
+    }
+
+    ///Downloads a file from the camera to disk, streaming it so that large chapters
+    ///are never held in memory
+    ///
+    ///The file is written to `destination` with `.part` appended until it is complete.
+    ///If the connection drops the download resumes where it stopped, and a `.part`
+    ///file left by an earlier call is resumed rather than downloaded again
+    ///
+    /// # Arguments
+    /// * `file` - The file to download, as returned by [`GoProHttp::media_list`]
+    /// * `destination` - Where to save the file
+    /// * `progress` - Called with the progress of the download every time data arrives
+    ///
+    /// # Returns
+    /// The size of the downloaded file
+    ///
+    /// # Errors
+    /// If the download is interrupted too many times, or its size doesn't match the
+    /// media list. For grouped media, whose size in the media list is that of the
+    /// whole group, the size is checked against what the camera sends instead
+    pub async fn download(
+        &self,
+        file: &MediaFile,
+        destination: impl AsRef<Path>,
+        progress: impl FnMut(&DownloadProgress),
+    ) -> Result<u64, Box<dyn Error>> {
+        download::download(self, file, destination.as_ref(), progress).await
+    }
+
+    ///Downloads the low resolution proxy (LRV) of a video, the same way as [`Self::download`]
+    ///
+    /// # Errors
+    /// If the file has no proxy, see [`MediaFile::lrv_name`]
+    pub async fn download_lrv(
+        &self,
+        file: &MediaFile,
+        destination: impl AsRef<Path>,
+        progress: impl FnMut(&DownloadProgress),
+    ) -> Result<u64, Box<dyn Error>> {
+        let (Some(name), Some(size)) = (file.lrv_name(), file.lrv_size) else {
+            return Err(format!("{} has no low resolution proxy", file.path()).into());
+        };
+        let proxy = MediaFile {
+            name,
+            size,
+            group: None,
+            ..file.clone()
+        };
+        download::download(self, &proxy, destination.as_ref(), progress).await
+    }
+
+    ///Downloads the media on the camera that hasn't been downloaded to a directory yet
+    ///
+    ///Files are saved to `<directory>/<serial number>/<camera directory>/<name>`, with
+    ///every member of grouped media saved separately. A manifest of what has been
+    ///downloaded from each camera is kept in the directory, see [`SYNC_MANIFEST_FILE_NAME`]
+    ///
+    ///A file that fails to download doesn't stop the others, and is reported in
+    ///[`SyncReport::failed`] to be retried by the next sync
+    ///
+    /// # Arguments
+    /// * `directory` - The local directory to sync to, e.g. `./footage`
+    /// * `options` - Whether this is a dry run, whether to delete from the camera, and
+    ///   how many files to download at once
+    ///
+    ///[`SYNC_MANIFEST_FILE_NAME`]: crate::SYNC_MANIFEST_FILE_NAME
+    pub async fn sync(
+        &self,
+        directory: impl AsRef<Path>,
+        options: &SyncOptions,
+    ) -> Result<SyncReport, Box<dyn Error>> {
+        sync::sync(self, directory.as_ref(), options).await
+    }
+}
+
+///Interprets the statuses in the camera state JSON, skipping any this crate doesn't know
+pub(crate) fn parse_statuses(state: &Value) -> HashMap<StatusID, QRI> {
+    let Some(statuses) = state["status"].as_object() else {
+        return HashMap::new();
+    };
+    statuses
+        .iter()
+        .filter_map(|(id, value)| {
+            let id = StatusID::try_from(id.parse::<u8>().ok()?).ok()?;
+            let interpretation = match (id.kind(), value) {
+                (StatusKind::String, Value::String(s)) => QRI::String(s.clone()),
+                (_, value) => id.interpret_integer(value.as_u64()?)?,
+            };
+            Some((id, interpretation))
+        })
+        .collect()
+}
+
+///Reads the setting and option IDs in the camera state JSON
+pub(crate) fn parse_settings(state: &Value) -> HashMap<u8, u32> {
+    let Some(settings) = state["settings"].as_object() else {
+        return HashMap::new();
+    };
+    settings
+        .iter()
+        .filter_map(|(id, value)| Some((id.parse().ok()?, value.as_u64()?.try_into().ok()?)))
+        .collect()
+}
+
+#[cfg(test)]
+use crate::mock_http::{self, Response};
+
+#[tokio::test]
+async fn test_core_endpoints() {
+    let (base_url, log) = mock_http::serve(|request| match request.path.as_str() {
+        "/gopro/camera/info" => Response::ok(
+            r#"{"model_number": 58, "model_name": "HERO11 Black", "firmware_version": "H22.01.01.10.70",
+                "serial_number": "C3461324698034", "ap_mac_addr": "065747046ceb", "ap_ssid": "GP24500456"}"#,
+        ),
+        "/gopro/camera/state" => Response::ok(r#"{"status": {"8": 0}, "settings": {"2": 1}}"#),
+        "/gopro/camera/presets/load?id=999" => Response::status(500),
+        _ => Response::ok("{}"),
+    })
+    .await;
+    let gopro = GoProHttp::with_base_url(format!("{base_url}/")).unwrap();
+
+    let info = gopro.camera_info().await.unwrap();
+    assert_eq!(info.model_number, 58);
+    assert_eq!(info.ap_ssid, "GP24500456");
+
+    let state = gopro.camera_state().await.unwrap();
+    assert_eq!(state["settings"]["2"], 1);
+
+    gopro.keep_alive().await.unwrap();
+    gopro.set_shutter(true).await.unwrap();
+    gopro.set_shutter(false).await.unwrap();
+    gopro.load_preset(65536).await.unwrap();
+    assert!(gopro.load_preset(999).await.is_err());
+    gopro.set_setting_option(2, 9).await.unwrap();
+
+    let paths: Vec<String> = log.lock().unwrap().iter().map(|r| r.path.clone()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/gopro/camera/info",
+            "/gopro/camera/state",
+            "/gopro/camera/keep_alive",
+            "/gopro/camera/shutter/start",
+            "/gopro/camera/shutter/stop",
+            "/gopro/camera/presets/load?id=65536",
+            "/gopro/camera/presets/load?id=999",
+            "/gopro/camera/setting?setting=2&option=9",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_media_list() {
+    let (base_url, _) = mock_http::serve(|request| match request.path.as_str() {
+        "/gopro/media/list" => Response::ok(crate::media::MEDIA_LIST_FIXTURE),
+        _ => Response::status(404),
+    })
+    .await;
+    let gopro = GoProHttp::with_base_url(base_url).unwrap();
+    let directories = gopro.media_list().await.unwrap();
+    let names: Vec<&str> = directories
+        .iter()
+        .flat_map(|d| d.files.iter().map(|f| f.name.as_str()))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "GOPR0001.JPG",
+            "GX010002.MP4",
+            "G0010003.JPG",
+            "G0020001.JPG"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_previews() {
+    let (base_url, log) = mock_http::serve(|request| match request.path.as_str() {
+        "/gopro/media/list" => Response::ok(crate::media::MEDIA_LIST_FIXTURE),
+        "/gopro/media/thumbnail?path=100GOPRO%2FGX010002.MP4" => Response::ok(vec![0xFF, 0xD8]),
+        "/gopro/media/screennail?path=100GOPRO%2FGX010002.MP4" => {
+            Response::ok(vec![0xFF, 0xD8, 0xFF])
+        }
+        "/gopro/media/info?path=100GOPRO%2FGX010002.MP4" => {
+            Response::ok(include_str!("../tests/fixtures/media_info.json"))
+        }
+        "/videos/DCIM/100GOPRO/GL010002.LRV" => Response::ok(vec![7; 1512345]),
+        _ => Response::status(404),
+    })
+    .await;
+    let gopro = GoProHttp::with_base_url(base_url).unwrap();
+    let directories = gopro.media_list().await.unwrap();
+    let (photo, video) = (&directories[0].files[0], &directories[0].files[1]);
+
+    assert_eq!(gopro.thumbnail(video).await.unwrap(), vec![0xFF, 0xD8]);
+    assert_eq!(gopro.screennail(video).await.unwrap().len(), 3);
+    assert_eq!(gopro.media_info(video).await.unwrap().width, Some(3840));
+    assert!(gopro.thumbnail(photo).await.is_err());
+
+    let destination = std::env::temp_dir().join(format!("gopro-lrv-{}.LRV", std::process::id()));
+    let size = gopro
+        .download_lrv(video, &destination, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(size, 1512345);
+    std::fs::remove_file(&destination).unwrap();
+    assert!(gopro
+        .download_lrv(photo, &destination, |_| {})
+        .await
+        .is_err());
+    assert!(log
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|r| !r.path.contains("GOPR0001.LRV")));
+}
+
+#[tokio::test]
+async fn test_recordings() {
+    let (base_url, _) = mock_http::serve(|request| match request.path.as_str() {
+        "/gopro/media/list" => Response::ok(
+            r#"{"media": [{"d": "100GOPRO", "fs": [
+                {"n": "GX020123.MP4", "cre": "2", "mod": "2", "s": "200"},
+                {"n": "GX010123.MP4", "cre": "1", "mod": "1", "s": "400"}
+            ]}]}"#,
+        ),
+        "/gopro/media/info?path=100GOPRO%2FGX010123.MP4" => {
+            Response::ok(r#"{"s": "400", "dur": "531", "hc": "1", "hi": [2002]}"#)
+        }
+        "/gopro/media/info?path=100GOPRO%2FGX020123.MP4" => {
+            Response::ok(r#"{"s": "200", "dur": "12", "hc": "2", "hi": ["500", 9510]}"#)
+        }
+        _ => Response::status(404),
+    })
+    .await;
+    let gopro = GoProHttp::with_base_url(base_url).unwrap();
+    let recordings = gopro.recordings().await.unwrap();
+    assert_eq!(recordings.len(), 1);
+    assert_eq!(recordings[0].size(), 600);
+    assert_eq!(recordings[0].duration, Some(Duration::from_secs(543)));
+    assert_eq!(
+        recordings[0].hilights,
+        Some(vec![
+            Duration::from_millis(2002),
+            Duration::from_millis(531500),
+            Duration::from_millis(540510)
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_delete() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let encoding = std::sync::Arc::new(AtomicBool::new(false));
+    let deleted = AtomicBool::new(false);
+    let camera_encoding = encoding.clone();
+    let (base_url, log) = mock_http::serve(move |request| match request.path.as_str() {
+        "/gopro/camera/state" => {
+            let (space, photos) = match deleted.load(Ordering::SeqCst) {
+                true => (2000, 0),
+                false => (1000, 2),
+            };
+            let encoding = camera_encoding.load(Ordering::SeqCst) as u8;
+            Response::ok(format!(
+                r#"{{"status": {{"10": {encoding}, "54": {space}, "38": {photos}, "39": 1}}}}"#
+            ))
+        }
+        "/gopro/media/list" => Response::ok(crate::media::MEDIA_LIST_FIXTURE),
+        path if path.starts_with("/gopro/media/delete/") => {
+            deleted.store(true, Ordering::SeqCst);
+            Response::ok("{}")
+        }
+        _ => Response::status(404),
+    })
+    .await;
+    let gopro = GoProHttp::with_base_url(base_url).unwrap();
+    let directories = gopro.media_list().await.unwrap();
+    let (photo, burst) = (&directories[0].files[0], &directories[0].files[2]);
+
+    assert_eq!(
+        gopro.storage_status().await.unwrap(),
+        StorageStatus {
+            remaining_space_kb: 1000,
+            total_videos: 1,
+            total_photos: 2
+        }
+    );
+    let after = gopro.delete_file(photo).await.unwrap();
+    assert_eq!((after.remaining_space_kb, after.total_photos), (2000, 0));
+    gopro.delete_group(burst).await.unwrap();
+    assert!(gopro.delete_group(photo).await.is_err());
+
+    encoding.store(true, Ordering::SeqCst);
+    assert!(gopro.delete_file(photo).await.is_err());
+    assert!(gopro.delete_all().await.is_err());
+    encoding.store(false, Ordering::SeqCst);
+    gopro.delete_all().await.unwrap();
+
+    let deletes: Vec<String> = log
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|r| r.path.starts_with("/gopro/media/delete/"))
+        .map(|r| r.path.clone())
+        .collect();
+    assert_eq!(
+        deletes,
+        vec![
+            "/gopro/media/delete/file?path=100GOPRO%2FGOPR0001.JPG",
+            "/gopro/media/delete/group?path=100GOPRO%2FG0010003.JPG",
+            "/gopro/media/delete/file?path=100GOPRO%2FGOPR0001.JPG",
+            "/gopro/media/delete/file?path=100GOPRO%2FGX010002.MP4",
+            "/gopro/media/delete/group?path=100GOPRO%2FG0010003.JPG",
+            "/gopro/media/delete/group?path=101GOPRO%2FG0020001.JPG",
+        ]
+    );
+}
+
+#[cfg(feature = "settings")]
+#[tokio::test]
+async fn test_set_setting() {
+    use crate::settings::Hero11Resolution;
+
+    let (base_url, log) = mock_http::serve(|_| Response::ok("{}")).await;
+    let gopro = GoProHttp::with_base_url(base_url).unwrap();
+    gopro
+        .set_setting(&GoProSetting::Resolution(Hero11Resolution::Res5_3K))
+        .await
+        .unwrap();
+    assert_eq!(
+        log.lock().unwrap()[0].path,
+        "/gopro/camera/setting?setting=2&option=100"
+    );
+}
+
+#[tokio::test]
+async fn test_send_command() {
+    let (base_url, log) = mock_http::serve(|_| Response::ok("{}")).await;
+    let gopro = GoProHttp::with_base_url(base_url).unwrap();
+    gopro.send_command(&GoProCommand::PhotoMode).await.unwrap();
+    gopro
+        .send_command(&GoProCommand::AddHilightDuringEncoding)
+        .await
+        .unwrap();
+    assert!(gopro.send_command(&GoProCommand::Sleep).await.is_err());
+
+    let paths: Vec<String> = log.lock().unwrap().iter().map(|r| r.path.clone()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/gopro/camera/presets/set_group?id=1001",
+            "/gopro/media/hilight/moment"
+        ]
+    );
+}
+
+#[test]
+fn test_parse_state() {
+    let state: Value = serde_json::from_str(
+        r#"{"status": {"8": 1, "30": "GP24500456", "70": 87, "13": 42, "200": 1},
+            "settings": {"2": 100, "3": 8}}"#,
+    )
+    .unwrap();
+
+    let statuses = parse_statuses(&state);
+    assert_eq!(statuses[&StatusID::SystemBusy], QRI::Bool(true));
+    assert_eq!(
+        statuses[&StatusID::ApSSID],
+        QRI::String("GP24500456".into())
+    );
+    assert_eq!(
+        statuses[&StatusID::InternalBatteryPercentage],
+        QRI::Percentage(87)
+    );
+    assert_eq!(
+        statuses[&StatusID::VideoProgressCounter].as_integer(),
+        Some(42)
+    );
+    // Unknown statuses are skipped
+    assert_eq!(statuses.len(), 4);
+
+    let settings = parse_settings(&state);
+    assert_eq!(settings[&2], 100);
+    assert_eq!(settings[&3], 8);
+}
@@ -0,0 +1,548 @@
+use crate::photo::PhotoCapture;
+use crate::query::StatusID;
+use crate::GoPro;
+use futures::future::LocalBoxFuture;
+use futures::stream::{self, Stream};
+use std::error::Error;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+///Upper bound on how many slots are considered when looking for the next one
+///inside the schedule, so that a window no slot can land in ends the schedule
+const MAX_SLOT_SEARCH: usize = 10_000;
+
+///A source of wall clock time that the intervalometer schedules against
+///
+///Implemented by [`SystemClock`], and by test clocks that jump forward instead of sleeping
+pub trait Clock {
+    ///Returns the current time
+    fn now(&self) -> SystemTime;
+    ///Waits until the given time, returning immediately if it has already passed
+    fn sleep_until(&self, deadline: SystemTime) -> LocalBoxFuture<'_, ()>;
+}
+
+///The real clock, sleeping with tokio
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep_until(&self, deadline: SystemTime) -> LocalBoxFuture<'_, ()> {
+        let wait = deadline
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+        Box::pin(tokio::time::sleep(wait))
+    }
+}
+
+///What the intervalometer needs to know about the camera before each shot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CameraConditions {
+    ///The camera is ready for commands (not busy and not encoding)
+    pub ready: bool,
+    ///The camera is reporting that it is too hot
+    pub hot: bool,
+    ///The internal battery level in percent
+    pub battery_percent: u8,
+}
+
+///Behavior the intervalometer needs from a camera
+///
+///Implemented by [`GoPro`], and by mock cameras in tests
+pub trait IntervalometerCamera {
+    ///Reports the current state of the camera
+    fn conditions(&self) -> LocalBoxFuture<'_, Result<CameraConditions, Box<dyn Error>>>;
+    ///Captures a single shot, returning the photo counts if a photo was taken
+    fn capture<'a>(
+        &'a self,
+        capture: &'a Capture,
+    ) -> LocalBoxFuture<'a, Result<Option<PhotoCapture>, Box<dyn Error>>>;
+}
+
+impl IntervalometerCamera for GoPro {
+    fn conditions(&self) -> LocalBoxFuture<'_, Result<CameraConditions, Box<dyn Error>>> {
+        Box::pin(async move {
+            let (ready, hot) = {
+                let status = self.status_monitor.subscribe();
+                let status = status.borrow();
+                (
+                    status.is_ready(),
+                    status.flag(StatusID::SystemHot) == Some(true),
+                )
+            };
+            let battery = self
+                .query_status_integer(StatusID::InternalBatteryPercentage)
+                .await?;
+            Ok(CameraConditions {
+                ready,
+                hot,
+                battery_percent: battery.min(100) as u8,
+            })
+        })
+    }
+
+    fn capture<'a>(
+        &'a self,
+        capture: &'a Capture,
+    ) -> LocalBoxFuture<'a, Result<Option<PhotoCapture>, Box<dyn Error>>> {
+        Box::pin(async move {
+            match capture {
+                Capture::Photo => Ok(Some(self.take_photo().await?)),
+                Capture::Clip(length) => {
+                    self.record_for(*length).await?;
+                    Ok(None)
+                }
+            }
+        })
+    }
+}
+
+///What to capture on each shot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capture {
+    ///A photo using the active photo preset
+    Photo,
+    ///A video clip of the given length
+    Clip(Duration),
+}
+
+///A time of day window that shots are restricted to, such as to avoid quiet hours
+///
+///A window whose end is before its start wraps past midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyWindow {
+    ///The time after midnight that the window opens
+    pub start: Duration,
+    ///The time after midnight that the window closes
+    pub end: Duration,
+    ///The offset from UTC in seconds of the time zone that the window is in
+    pub utc_offset_secs: i32,
+}
+
+impl DailyWindow {
+    ///Returns the time after local midnight of the given time
+    fn time_of_day(&self, time: SystemTime) -> u64 {
+        let since_epoch = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        (since_epoch + self.utc_offset_secs as i64).rem_euclid(SECONDS_PER_DAY as i64) as u64
+    }
+
+    ///Returns true if the time falls inside the window
+    fn contains(&self, time: SystemTime) -> bool {
+        let (start, end) = (self.start.as_secs(), self.end.as_secs());
+        let time_of_day = self.time_of_day(time);
+        if start <= end {
+            start <= time_of_day && time_of_day < end
+        } else {
+            time_of_day >= start || time_of_day < end
+        }
+    }
+
+    ///Returns the next time at or after the given time that the window opens
+    fn next_opening(&self, time: SystemTime) -> SystemTime {
+        let start = self.start.as_secs();
+        let time_of_day = self.time_of_day(time);
+        let wait = if time_of_day <= start {
+            start - time_of_day
+        } else {
+            SECONDS_PER_DAY - time_of_day + start
+        };
+        time + Duration::from_secs(wait)
+    }
+}
+
+///When the intervalometer takes its shots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    ///The time between shots, which must be greater than zero
+    pub interval: Duration,
+    ///When to take the first shot, immediately if None
+    pub start: Option<SystemTime>,
+    ///When to stop, never if None
+    pub end: Option<SystemTime>,
+    ///Restricts shots to a window of each day
+    pub daily_window: Option<DailyWindow>,
+    ///The number of shots to capture before stopping, unlimited if None
+    ///
+    ///Skipped shots don't count towards this
+    pub max_shots: Option<u32>,
+}
+
+impl Schedule {
+    ///Creates a schedule that shoots at a fixed interval forever, starting immediately
+    pub fn every(interval: Duration) -> Self {
+        Schedule {
+            interval,
+            start: None,
+            end: None,
+            daily_window: None,
+            max_shots: None,
+        }
+    }
+
+    ///Returns the time of slot `index`, where slots are spaced an interval apart from `anchor`
+    fn slot_time(&self, anchor: SystemTime, index: u64) -> SystemTime {
+        anchor + Duration::from_nanos((self.interval.as_nanos() * index as u128) as u64)
+    }
+
+    ///Returns the index of the first slot at or after the given time
+    fn slot_at_or_after(&self, anchor: SystemTime, time: SystemTime) -> u64 {
+        let since_anchor = time.duration_since(anchor).unwrap_or_default().as_nanos();
+        since_anchor.div_ceil(self.interval.as_nanos()) as u64
+    }
+
+    ///Finds the first slot from `index` onwards that is inside the schedule
+    ///
+    /// # Returns
+    /// * `Some((index, time))` - The slot and the time it should be taken at
+    /// * `None` - If the schedule has ended
+    fn next_slot(&self, anchor: SystemTime, mut index: u64) -> Option<(u64, SystemTime)> {
+        for _ in 0..MAX_SLOT_SEARCH {
+            let time = self.slot_time(anchor, index);
+            if self.end.is_some_and(|end| time > end) {
+                return None;
+            }
+            match self.daily_window {
+                Some(window) if !window.contains(time) => {
+                    index = self
+                        .slot_at_or_after(anchor, window.next_opening(time))
+                        .max(index + 1);
+                }
+                _ => return Some((index, time)),
+            }
+        }
+        None
+    }
+}
+
+///Why a shot was not taken because the intervalometer is paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    ///The battery is below the minimum level, holding the level in percent
+    LowBattery(u8),
+    ///The camera is too hot
+    Overheated,
+}
+
+///What happened at a scheduled shot
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShotOutcome {
+    ///The shot was captured, with the photo counts if it was a photo
+    Captured(Option<PhotoCapture>),
+    ///The camera was busy so the shot was skipped
+    SkippedBusy,
+    ///The intervalometer is paused so the shot was skipped
+    Paused(PauseReason),
+    ///Checking the camera or capturing failed
+    Failed(String),
+}
+
+///The result of a single scheduled shot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShotResult {
+    ///The index of the slot in the schedule
+    pub slot: u64,
+    ///When the shot was scheduled for
+    pub scheduled: SystemTime,
+    ///What happened
+    pub outcome: ShotOutcome,
+}
+
+///Takes photos or clips on a [`Schedule`]
+///
+///Shots are skipped while the camera is busy, and the intervalometer pauses while
+///the battery is low or the camera is too hot. Shots that are missed because a
+///previous one overran are skipped rather than taken late
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Intervalometer {
+    ///When to take shots
+    pub schedule: Schedule,
+    ///What to capture on each shot
+    pub capture: Capture,
+    ///The battery level in percent below which the intervalometer pauses
+    pub min_battery_percent: u8,
+}
+
+///Progress through the schedule while the result stream is polled
+struct RunState {
+    anchor: Option<SystemTime>,
+    next_index: u64,
+    shots: u32,
+}
+
+impl Intervalometer {
+    ///Runs the intervalometer against the real clock
+    ///
+    ///Nothing happens until the returned stream is polled, and it ends with the schedule
+    ///
+    /// # Arguments
+    /// * `camera` - The camera to take shots with
+    pub fn run<'a, C: IntervalometerCamera>(
+        &'a self,
+        camera: &'a C,
+    ) -> impl Stream<Item = ShotResult> + 'a {
+        self.run_with_clock(camera, &SystemClock)
+    }
+
+    ///Runs the intervalometer against the given clock
+    ///
+    /// # Arguments
+    /// * `camera` - The camera to take shots with
+    /// * `clock` - The clock to schedule against
+    pub fn run_with_clock<'a, C: IntervalometerCamera, K: Clock>(
+        &'a self,
+        camera: &'a C,
+        clock: &'a K,
+    ) -> impl Stream<Item = ShotResult> + 'a {
+        let state = RunState {
+            anchor: None,
+            next_index: 0,
+            shots: 0,
+        };
+        stream::unfold(state, move |mut state| async move {
+            if self.schedule.interval.is_zero()
+                || self
+                    .schedule
+                    .max_shots
+                    .is_some_and(|max| state.shots >= max)
+            {
+                return None;
+            }
+            let anchor = *state
+                .anchor
+                .get_or_insert_with(|| self.schedule.start.unwrap_or_else(|| clock.now()));
+
+            //Skip slots that have already passed rather than taking them late
+            let earliest = self.schedule.slot_at_or_after(anchor, clock.now());
+            let (slot, scheduled) = self
+                .schedule
+                .next_slot(anchor, state.next_index.max(earliest))?;
+            clock.sleep_until(scheduled).await;
+
+            let outcome = self.shoot(camera).await;
+            if let ShotOutcome::Captured(_) = outcome {
+                state.shots += 1;
+            }
+            state.next_index = slot + 1;
+
+            let result = ShotResult {
+                slot,
+                scheduled,
+                outcome,
+            };
+            Some((result, state))
+        })
+    }
+
+    ///Checks the camera and takes a shot if it is in a state to
+    async fn shoot<C: IntervalometerCamera>(&self, camera: &C) -> ShotOutcome {
+        let conditions = match camera.conditions().await {
+            Ok(conditions) => conditions,
+            Err(e) => return ShotOutcome::Failed(e.to_string()),
+        };
+        if conditions.hot {
+            return ShotOutcome::Paused(PauseReason::Overheated);
+        }
+        if conditions.battery_percent < self.min_battery_percent {
+            return ShotOutcome::Paused(PauseReason::LowBattery(conditions.battery_percent));
+        }
+        if !conditions.ready {
+            return ShotOutcome::SkippedBusy;
+        }
+        match camera.capture(&self.capture).await {
+            Ok(photo) => ShotOutcome::Captured(photo),
+            Err(e) => ShotOutcome::Failed(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+use futures::stream::StreamExt;
+#[cfg(test)]
+use std::cell::{Cell, RefCell};
+
+///A clock that jumps to the deadline instead of sleeping
+#[cfg(test)]
+struct MockClock {
+    now: Cell<SystemTime>,
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        self.now.get()
+    }
+
+    fn sleep_until(&self, deadline: SystemTime) -> LocalBoxFuture<'_, ()> {
+        if deadline > self.now.get() {
+            self.now.set(deadline);
+        }
+        Box::pin(async {})
+    }
+}
+
+///A camera that reports a scripted sequence of conditions, with each capture
+///taking a fixed amount of time on the clock
+#[cfg(test)]
+struct MockCamera<'a> {
+    clock: &'a MockClock,
+    conditions: RefCell<Vec<CameraConditions>>,
+    capture_time: Duration,
+}
+
+#[cfg(test)]
+impl IntervalometerCamera for MockCamera<'_> {
+    fn conditions(&self) -> LocalBoxFuture<'_, Result<CameraConditions, Box<dyn Error>>> {
+        let mut conditions = self.conditions.borrow_mut();
+        let next = if conditions.len() > 1 {
+            conditions.remove(0)
+        } else {
+            conditions[0]
+        };
+        Box::pin(async move { Ok(next) })
+    }
+
+    fn capture<'a>(
+        &'a self,
+        _capture: &'a Capture,
+    ) -> LocalBoxFuture<'a, Result<Option<PhotoCapture>, Box<dyn Error>>> {
+        self.clock.now.set(self.clock.now.get() + self.capture_time);
+        Box::pin(async { Ok(None) })
+    }
+}
+
+#[cfg(test)]
+const GOOD: CameraConditions = CameraConditions {
+    ready: true,
+    hot: false,
+    battery_percent: 80,
+};
+
+#[tokio::test]
+async fn test_fixed_interval_with_max_shots() {
+    let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let clock = MockClock {
+        now: Cell::new(start),
+    };
+    let camera = MockCamera {
+        clock: &clock,
+        conditions: RefCell::new(vec![GOOD]),
+        capture_time: Duration::from_secs(1),
+    };
+    let intervalometer = Intervalometer {
+        schedule: Schedule {
+            max_shots: Some(3),
+            ..Schedule::every(Duration::from_secs(10))
+        },
+        capture: Capture::Photo,
+        min_battery_percent: 20,
+    };
+
+    let results: Vec<ShotResult> = intervalometer
+        .run_with_clock(&camera, &clock)
+        .collect()
+        .await;
+    let times: Vec<SystemTime> = results.iter().map(|r| r.scheduled).collect();
+    assert_eq!(
+        times,
+        vec![
+            start,
+            start + Duration::from_secs(10),
+            start + Duration::from_secs(20)
+        ]
+    );
+    assert!(results
+        .iter()
+        .all(|r| r.outcome == ShotOutcome::Captured(None)));
+}
+
+#[tokio::test]
+async fn test_skips_pauses_and_overruns() {
+    let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let clock = MockClock {
+        now: Cell::new(start),
+    };
+    let busy = CameraConditions {
+        ready: false,
+        ..GOOD
+    };
+    let hot = CameraConditions { hot: true, ..GOOD };
+    let low = CameraConditions {
+        battery_percent: 5,
+        ..GOOD
+    };
+    let camera = MockCamera {
+        clock: &clock,
+        conditions: RefCell::new(vec![busy, hot, low, GOOD]),
+        // Each capture overruns the next slot
+        capture_time: Duration::from_secs(15),
+    };
+    let intervalometer = Intervalometer {
+        schedule: Schedule {
+            end: Some(start + Duration::from_secs(60)),
+            ..Schedule::every(Duration::from_secs(10))
+        },
+        capture: Capture::Clip(Duration::from_secs(15)),
+        min_battery_percent: 20,
+    };
+
+    let results: Vec<ShotResult> = intervalometer
+        .run_with_clock(&camera, &clock)
+        .collect()
+        .await;
+    let outcomes: Vec<(u64, ShotOutcome)> =
+        results.into_iter().map(|r| (r.slot, r.outcome)).collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            (0, ShotOutcome::SkippedBusy),
+            (1, ShotOutcome::Paused(PauseReason::Overheated)),
+            (2, ShotOutcome::Paused(PauseReason::LowBattery(5))),
+            (3, ShotOutcome::Captured(None)),
+            // Slot 4 was missed while capturing slot 3
+            (5, ShotOutcome::Captured(None)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_daily_window() {
+    // Midnight UTC
+    let midnight = UNIX_EPOCH + Duration::from_secs(19_000 * SECONDS_PER_DAY);
+    let clock = MockClock {
+        now: Cell::new(midnight),
+    };
+    let camera = MockCamera {
+        clock: &clock,
+        conditions: RefCell::new(vec![GOOD]),
+        capture_time: Duration::ZERO,
+    };
+    // Shoot hourly, but only between 22:00 and 02:00 in UTC+1
+    let intervalometer = Intervalometer {
+        schedule: Schedule {
+            max_shots: Some(5),
+            daily_window: Some(DailyWindow {
+                start: Duration::from_secs(22 * 3600),
+                end: Duration::from_secs(2 * 3600),
+                utc_offset_secs: 3600,
+            }),
+            ..Schedule::every(Duration::from_secs(3600))
+        },
+        capture: Capture::Photo,
+        min_battery_percent: 0,
+    };
+
+    let hours: Vec<u64> = intervalometer
+        .run_with_clock(&camera, &clock)
+        .map(|r| r.scheduled.duration_since(midnight).unwrap().as_secs() / 3600)
+        .collect()
+        .await;
+    // 00:00 UTC is 01:00 local, so inside the window, then it closes at 01:00 UTC
+    // and reopens at 21:00 UTC
+    assert_eq!(hours, vec![0, 21, 22, 23, 24]);
+}
@@ -0,0 +1,94 @@
+use crate::services::{GoProControlAndQueryCharacteristics as GPCharac, ToUUID};
+use btleplug::api::Characteristic;
+use btleplug::platform::Peripheral;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+
+///The interval at which the spec recommends sending keep alives
+pub const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(3);
+
+///The keep alive setting (ID 91) and its expected response
+///
+///NOTE: The byte arrays here were taken directly from the GoPro Open Spec:
+///<https://gopro.github.io/OpenGoPro/ble_2_0#keep-alive>
+const KEEP_ALIVE_BYTES: &[u8] = &[0x03, 0x5B, 0x01, 0x42];
+const KEEP_ALIVE_RESPONSE_BYTES: &[u8] = &[0x02, 0x5B, 0x00];
+
+///How many events a slow subscriber can fall behind before it starts missing them
+const EVENT_CAPACITY: usize = 16;
+
+///Represents something that happened in the keep alive background task
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeepAliveEvent {
+    ///The camera acknowledged a keep alive
+    Sent,
+    ///A keep alive couldn't be sent or wasn't acknowledged correctly
+    Failed(String),
+}
+
+///Handle to the background task that periodically sends keep alives to a GoPro
+pub(crate) struct KeepAlive {
+    task: JoinHandle<()>,
+    events: broadcast::Sender<KeepAliveEvent>,
+}
+
+impl KeepAlive {
+    ///Spawns the keep alive task
+    ///
+    /// # Arguments
+    /// * `device` - The GoPro peripheral to keep awake
+    /// * `exchange_lock` - The lock shared with the GoPro handle
+    /// * `settings` - The settings characteristic the keep alive is written to
+    /// * `interval` - How often to send a keep alive (must be non zero)
+    pub(crate) fn spawn(
+        device: Peripheral,
+        exchange_lock: Arc<Mutex<()>>,
+        settings: Characteristic,
+        interval: Duration,
+    ) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let task_events = events.clone();
+
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let event = match crate::exchange(
+                    &device,
+                    &exchange_lock,
+                    &settings,
+                    KEEP_ALIVE_BYTES,
+                    GPCharac::SettingsResponse.to_uuid(),
+                )
+                .await
+                {
+                    Ok(res) if res.value == KEEP_ALIVE_RESPONSE_BYTES => KeepAliveEvent::Sent,
+                    Ok(_) => KeepAliveEvent::Failed("Response from GoPro was incorrect".into()),
+                    Err(e) => KeepAliveEvent::Failed(e.to_string()),
+                };
+                //No subscribers is not an error, the events are opt in
+                let _ = task_events.send(event);
+            }
+        });
+
+        KeepAlive { task, events }
+    }
+
+    ///Returns a new receiver for the events emitted by the task
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<KeepAliveEvent> {
+        self.events.subscribe()
+    }
+
+    ///Stops the task, it will not send any further keep alives
+    pub(crate) fn stop(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for KeepAlive {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -1,28 +1,150 @@
+#[cfg(feature = "wifi")]
+mod chapters;
+#[cfg(feature = "wifi")]
+mod cohn;
 mod command;
+#[cfg(feature = "wifi")]
+mod controller;
+#[cfg(feature = "wifi")]
+mod download;
+#[cfg(feature = "media")]
+mod export;
+#[cfg(feature = "media")]
+mod gpmf;
+#[cfg(feature = "wifi")]
+mod http;
+#[cfg(feature = "query")]
+mod intervalometer;
+mod keep_alive;
+#[cfg(feature = "wifi")]
+mod media;
+#[cfg(all(test, feature = "wifi"))]
+mod mock_http;
+#[cfg(feature = "media")]
+mod mp4;
+#[cfg(feature = "wifi")]
+mod network;
+mod packet;
+#[cfg(feature = "query")]
+mod photo;
+#[cfg(feature = "wifi")]
+mod protobuf;
 #[cfg(feature = "query")]
 mod query;
+#[cfg(feature = "query")]
+mod recording;
 mod services;
 #[cfg(feature = "settings")]
 mod settings;
+#[cfg(feature = "wifi")]
+mod snapshot;
+#[cfg(feature = "query")]
+mod status;
+#[cfg(feature = "wifi")]
+mod sync;
 #[cfg(test)]
 mod tests;
+#[cfg(feature = "wifi")]
+mod wifi;
+#[cfg(feature = "wifi")]
+pub use crate::chapters::{recordings, MediaEncoding, MediaName, Recording};
+#[cfg(feature = "wifi")]
+pub use crate::cohn::{CohnNetworkState, CohnStatus};
 pub use crate::command::GoProCommand;
+#[cfg(feature = "wifi")]
+pub use crate::controller::Controller;
+#[cfg(feature = "wifi")]
+pub use crate::download::DownloadProgress;
+#[cfg(feature = "media")]
+pub use crate::export::{export_csv, export_geojson, export_gpx};
+#[cfg(feature = "media")]
+pub use crate::gpmf::{parse_gpmf, read_telemetry, GpmfSample, GpmfStream, GpsSample, Telemetry};
+#[cfg(feature = "wifi")]
+pub use crate::http::{CameraInfo, GoProHttp, StorageStatus, DEFAULT_HTTP_BASE_URL};
+#[cfg(feature = "query")]
+pub use crate::intervalometer::{
+    CameraConditions, Capture, Clock, DailyWindow, Intervalometer, IntervalometerCamera,
+    PauseReason, Schedule, ShotOutcome, ShotResult, SystemClock,
+};
+pub use crate::keep_alive::{KeepAliveEvent, DEFAULT_KEEP_ALIVE_INTERVAL};
+#[cfg(feature = "wifi")]
+pub use crate::media::{MediaDirectory, MediaFile, MediaGroup, MediaGroupKind, MediaInfo};
+#[cfg(feature = "media")]
+pub use crate::mp4::{concat_chapters, read_hilights};
+#[cfg(feature = "wifi")]
+pub use crate::network::{
+    AccessPoint, AccessPointConnection, AccessPointScan, ProvisioningState, ScanState,
+};
+#[cfg(feature = "query")]
+pub use crate::photo::PhotoCapture;
 #[cfg(feature = "query")]
 pub use crate::query::{GoProQuery, QueryResponse, QueryResponseIntepretation};
+#[cfg(feature = "query")]
+pub use crate::recording::RecordingSession;
 pub use crate::services::{
     GoProControlAndQueryCharacteristics as GPCharac, GoProServices, Sendable, ToUUID,
 };
+#[cfg(feature = "wifi")]
+pub use crate::services::{GoProManagementCharacteristics, GoProWifiApCharacteristics};
 #[cfg(feature = "settings")]
 pub use crate::settings::GoProSetting;
+#[cfg(feature = "wifi")]
+pub use crate::snapshot::CameraSnapshot;
+#[cfg(feature = "query")]
+pub use crate::status::{CameraState, CameraStatus};
+#[cfg(feature = "wifi")]
+pub use crate::sync::{SyncOptions, SyncReport, SYNC_MANIFEST_FILE_NAME};
+#[cfg(feature = "wifi")]
+pub use crate::wifi::{CohnCredentials, WifiApCredentials};
 use btleplug::api::{Central, Manager as _, Peripheral as _, ScanFilter, WriteType};
-use btleplug::api::{CharPropFlags, ValueNotification};
+use btleplug::api::{CharPropFlags, Characteristic, Service, ValueNotification};
 use btleplug::platform::{Adapter, Manager, Peripheral};
+#[cfg(feature = "wifi")]
+use futures::stream::BoxStream;
 use futures::stream::StreamExt;
+use keep_alive::KeepAlive;
+use packet::Accumulator;
+use services::CharacteristicTable;
+#[cfg(feature = "query")]
+use status::StatusMonitor;
+use std::collections::BTreeSet;
 use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "query")]
+use tokio::sync::watch;
+use tokio::sync::{broadcast, Mutex};
+use uuid::Uuid;
+
+///How long to wait for the GoPro to respond to a command, setting or query
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
 
 ///Represents a connected GoPro device
 pub struct GoPro {
     device: Peripheral,
+    characteristics: CharacteristicTable,
+    ///Held for the duration of a write and its response so that background
+    ///tasks (such as the keep alive) can't consume another caller's response
+    exchange_lock: Arc<Mutex<()>>,
+    keep_alive: Option<KeepAlive>,
+    #[cfg(feature = "query")]
+    status_monitor: StatusMonitor,
+    #[cfg(feature = "query")]
+    wait_for_ready: Option<Duration>,
+}
+
+///Options controlling the behavior of a GoPro connection
+#[derive(Default)]
+pub struct ConnectOptions {
+    ///If set, a background task will send a keep alive to the camera at this
+    ///interval for as long as it is connected, preventing it from auto powering down
+    ///
+    ///The spec recommends [`DEFAULT_KEEP_ALIVE_INTERVAL`]
+    pub keep_alive: Option<Duration>,
+    #[cfg(feature = "query")]
+    ///If set, commands and settings will wait up to this long for the camera to be
+    ///ready (see [`GoPro::wait_until_ready`]) before being sent
+    pub wait_for_ready: Option<Duration>,
 }
 
 impl GoPro {
@@ -34,16 +156,9 @@ impl GoPro {
         &self,
         command: &GoProCommand,
     ) -> Result<(), Box<dyn Error>> {
-        let characteristics = self.device.characteristics();
-
-        let command_write_char = characteristics
-            .iter()
-            .find(|c| c.uuid == GPCharac::Command.to_uuid())
-            .unwrap();
-
         self.device
             .write(
-                &command_write_char,
+                &self.characteristics.command,
                 command.as_bytes(),
                 WriteType::WithoutResponse,
             )
@@ -57,15 +172,18 @@ impl GoPro {
     /// # Arguments
     /// * `command` - The command to send to the GoPro
     pub async fn send_command(&self, command: &GoProCommand) -> Result<(), Box<dyn Error>> {
-        self.send_command_unchecked(command).await?;
-        let res = self.get_next_notification().await?;
-        if res.is_none() {
-            return Err("No response from GoPro".into());
-        }
-        let res = res.unwrap();
-        if res.uuid != GPCharac::CommandResponse.to_uuid() {
-            return Err("Response from GoPro came from incorrect UUID".into());
+        #[cfg(feature = "query")]
+        if command.requires_ready() {
+            self.wait_for_ready_if_enabled().await?;
         }
+        let res = exchange(
+            &self.device,
+            &self.exchange_lock,
+            &self.characteristics.command,
+            command.as_bytes(),
+            GPCharac::CommandResponse.to_uuid(),
+        )
+        .await?;
         if res.value != command.response_value_bytes() {
             return Err("Response from GoPro was incorrect".into());
         }
@@ -81,16 +199,9 @@ impl GoPro {
         &self,
         setting: &GoProSetting,
     ) -> Result<(), Box<dyn Error>> {
-        let characteristics = self.device.characteristics();
-
-        let settings_write_char = characteristics
-            .iter()
-            .find(|c| c.uuid == GPCharac::Settings.to_uuid())
-            .unwrap();
-
         self.device
             .write(
-                &settings_write_char,
+                &self.characteristics.settings,
                 setting.as_bytes(),
                 WriteType::WithoutResponse,
             )
@@ -105,15 +216,16 @@ impl GoPro {
     /// # Arguments
     /// * `setting` - The setting to send to the GoPro
     pub async fn send_setting(&self, setting: &GoProSetting) -> Result<(), Box<dyn Error>> {
-        self.send_setting_unchecked(setting).await?;
-        let res = self.get_next_notification().await?;
-        if res.is_none() {
-            return Err("No response from GoPro".into());
-        }
-        let res = res.unwrap();
-        if res.uuid != GPCharac::SettingsResponse.to_uuid() {
-            return Err("Response from GoPro came from incorrect UUID".into());
-        }
+        #[cfg(feature = "query")]
+        self.wait_for_ready_if_enabled().await?;
+        let res = exchange(
+            &self.device,
+            &self.exchange_lock,
+            &self.characteristics.settings,
+            setting.as_bytes(),
+            GPCharac::SettingsResponse.to_uuid(),
+        )
+        .await?;
         if res.value != setting.response_value_bytes() {
             return Err("Response from GoPro was incorrect".into());
         }
@@ -126,29 +238,14 @@ impl GoPro {
     /// # Arguments
     /// * `query` - The query to send to the GoPro
     pub async fn query(&self, query: &GoProQuery) -> Result<QueryResponse, Box<dyn Error>> {
-        let characteristics = self.device.characteristics();
-
-        let query_write_char = characteristics
-            .iter()
-            .find(|c| c.uuid == GPCharac::Query.to_uuid())
-            .unwrap();
-
-        self.device
-            .write(
-                &query_write_char,
-                query.as_bytes().as_ref(),
-                WriteType::WithoutResponse,
-            )
-            .await?;
-
-        let res = self.get_next_notification().await?;
-        if res.is_none() {
-            return Err("No response from GoPro".into());
-        }
-        let res = res.unwrap();
-        if res.uuid != GPCharac::QueryResponse.to_uuid() {
-            return Err("Response from GoPro came from incorrect UUID".into());
-        }
+        let res = exchange(
+            &self.device,
+            &self.exchange_lock,
+            &self.characteristics.query,
+            query.as_bytes().as_ref(),
+            GPCharac::QueryResponse.to_uuid(),
+        )
+        .await?;
 
         let query_response = QueryResponse::deserialize(&res.value)?;
         Ok(query_response)
@@ -168,32 +265,333 @@ impl GoPro {
         &self,
         query: &GoProQuery,
     ) -> Result<Option<QueryResponseIntepretation>, Box<dyn Error>> {
-        let characteristics = self.device.characteristics();
+        let res = exchange(
+            &self.device,
+            &self.exchange_lock,
+            &self.characteristics.query,
+            query.as_bytes().as_ref(),
+            GPCharac::QueryResponse.to_uuid(),
+        )
+        .await?;
 
-        let query_write_char = characteristics
-            .iter()
-            .find(|c| c.uuid == GPCharac::Query.to_uuid())
-            .unwrap();
+        let query_response = QueryResponse::deserialize(&res.value)?;
+        Ok(query_response.interpret())
+    }
 
-        self.device
-            .write(
-                &query_write_char,
-                query.as_bytes().as_ref(),
-                WriteType::WithoutResponse,
-            )
+    #[cfg(feature = "query")]
+    ///Queries a single status and returns its value as an integer
+    ///
+    /// # Arguments
+    /// * `id` - The status to query, which must hold an integer like value
+    pub(crate) async fn query_status_integer(
+        &self,
+        id: query::StatusID,
+    ) -> Result<u64, Box<dyn Error>> {
+        let response = self
+            .interpreted_query(&GoProQuery::GetStatusValue(vec![id]))
+            .await?
+            .ok_or("Response from GoPro was uninterpretable")?;
+        Ok(response
+            .as_integer()
+            .ok_or("Response from GoPro was not an integer")?)
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Sends a query whose response holds any number of values and returns them all
+    pub(crate) async fn query_all(
+        &self,
+        query: &GoProQuery,
+    ) -> Result<Vec<QueryResponse>, Box<dyn Error>> {
+        let res = exchange(
+            &self.device,
+            &self.exchange_lock,
+            &self.characteristics.query,
+            &query.as_bytes(),
+            GPCharac::QueryResponse.to_uuid(),
+        )
+        .await?;
+        Ok(QueryResponse::deserialize_many(packet::payload(
+            &res.value,
+        ))?)
+    }
+
+    #[cfg(feature = "query")]
+    ///Starts recording video and returns a handle to the recording
+    ///
+    ///The recording is stopped with [`RecordingSession::stop`], or in the
+    ///background if the handle is dropped first
+    pub async fn start_recording(&self) -> Result<RecordingSession<'_>, Box<dyn Error>> {
+        self.send_command(GoProCommand::ShutterStart.as_ref())
             .await?;
+        Ok(RecordingSession::new(self))
+    }
 
-        let res = self.get_next_notification().await?;
-        if res.is_none() {
-            return Err("No response from GoPro".into());
+    #[cfg(feature = "query")]
+    ///Records video for the given duration, returning once the camera has finished encoding
+    ///
+    /// # Arguments
+    /// * `duration` - How long to record for
+    pub async fn record_for(&self, duration: Duration) -> Result<(), Box<dyn Error>> {
+        let session = self.start_recording().await?;
+        tokio::time::sleep(duration).await;
+        session.stop().await
+    }
+
+    #[cfg(feature = "query")]
+    ///Takes a photo, switching to photo mode first if needed, and waits for the camera
+    ///to finish capturing it
+    ///
+    ///Whichever photo preset is active is used, so this also covers burst and night
+    ///photos which can take several seconds to capture
+    ///
+    /// # Returns
+    /// The remaining and total photo counts reported once the capture finished
+    pub async fn take_photo(&self) -> Result<PhotoCapture, Box<dyn Error>> {
+        let group = self
+            .query_status_integer(query::StatusID::ActivePresetsGroup)
+            .await?;
+        if group != photo::PHOTO_PRESET_GROUP {
+            self.send_command(GoProCommand::PhotoMode.as_ref()).await?;
         }
-        let res = res.unwrap();
-        if res.uuid != GPCharac::QueryResponse.to_uuid() {
-            return Err("Response from GoPro came from incorrect UUID".into());
+        self.wait_until_ready(photo::CAPTURE_TIMEOUT).await?;
+
+        //Subscribe before firing so that the capture starting can't be missed
+        let mut status = self.status_monitor.subscribe();
+        status.borrow_and_update();
+        self.send_command(GoProCommand::ShutterStart.as_ref())
+            .await?;
+
+        //A capture may be over before it is seen starting, so only wait briefly for it
+        let _ = tokio::time::timeout(
+            photo::CAPTURE_START_TIMEOUT,
+            status.wait_for(|s| !s.is_ready()),
+        )
+        .await;
+        self.wait_until_ready(photo::CAPTURE_TIMEOUT).await?;
+
+        Ok(PhotoCapture {
+            remaining_photos: self
+                .query_status_integer(query::StatusID::RemainingPhotos)
+                .await?,
+            total_photos: self
+                .query_status_integer(query::StatusID::NumTotalPhotos)
+                .await?,
+        })
+    }
+
+    #[cfg(feature = "query")]
+    ///Waits until the GoPro reports that it is ready, not busy and not encoding
+    ///
+    ///The spec says that commands must not be sent while the camera is busy or encoding.
+    ///The status is kept up to date by updates the camera pushes, so this doesn't poll
+    ///
+    /// # Arguments
+    /// * `timeout` - The longest time to wait for the camera to become ready
+    pub async fn wait_until_ready(&self, timeout: Duration) -> Result<(), Box<dyn Error>> {
+        let mut status = self.status_monitor.subscribe();
+        let ready = tokio::time::timeout(timeout, status.wait_for(CameraStatus::is_ready)).await;
+        match ready {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(_)) => Err("Status updates from GoPro are unavailable".into()),
+            Err(_) => Err("Timed out waiting for GoPro to be ready".into()),
         }
+    }
 
-        let query_response = QueryResponse::deserialize(&res.value)?;
-        Ok(query_response.interpret())
+    #[cfg(feature = "query")]
+    ///Returns a receiver for the camera status that the GoPro handle keeps up to date
+    pub fn status(&self) -> watch::Receiver<CameraStatus> {
+        self.status_monitor.subscribe()
+    }
+
+    #[cfg(feature = "query")]
+    ///Returns a receiver for the overall state of the camera, which the GoPro handle
+    ///derives from the statuses the camera pushes and keeps up to date
+    ///
+    ///Use `changed()` on the receiver to react to state transitions
+    pub fn state(&self) -> watch::Receiver<CameraState> {
+        self.status_monitor.subscribe_state()
+    }
+
+    #[cfg(feature = "query")]
+    ///Sets whether commands and settings wait for the camera to be ready before being sent
+    ///
+    /// # Arguments
+    /// * `timeout` - How long to wait for the camera, or None to send immediately
+    pub fn set_wait_for_ready(&mut self, timeout: Option<Duration>) {
+        self.wait_for_ready = timeout;
+    }
+
+    #[cfg(feature = "query")]
+    ///Waits for the camera to be ready if waiting was opted into
+    async fn wait_for_ready_if_enabled(&self) -> Result<(), Box<dyn Error>> {
+        match self.wait_for_ready {
+            Some(timeout) => self.wait_until_ready(timeout).await,
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Turns the camera's WiFi access point on or off
+    ///
+    /// # Arguments
+    /// * `enable` - Whether the access point should be on
+    pub async fn enable_wifi_ap(&self, enable: bool) -> Result<(), Box<dyn Error>> {
+        let command = if enable {
+            GoProCommand::WifiApOn
+        } else {
+            GoProCommand::WifiApOff
+        };
+        self.send_command(&command).await
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Reads the SSID and password of the camera's WiFi access point
+    pub async fn wifi_ap_credentials(&self) -> Result<WifiApCredentials, Box<dyn Error>> {
+        Ok(WifiApCredentials {
+            ssid: self
+                .read_string(self.characteristics.wifi_ap_ssid.as_ref())
+                .await?,
+            password: self
+                .read_string(self.characteristics.wifi_ap_password.as_ref())
+                .await?,
+        })
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Reads a characteristic that holds a UTF-8 string
+    ///
+    /// # Arguments
+    /// * `read_char` - The characteristic to read, if it was found on the GoPro
+    async fn read_string(
+        &self,
+        read_char: Option<&Characteristic>,
+    ) -> Result<String, Box<dyn Error>> {
+        let read_char = read_char.ok_or("Characteristic not found on this GoPro")?;
+        let value = self.device.read(read_char).await?;
+        Ok(String::from_utf8(value)?)
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Has the GoPro create the certificate it uses for HTTPS on the home network (COHN)
+    ///
+    /// # Arguments
+    /// * `replace` - If true an existing certificate is replaced, otherwise it is kept
+    pub async fn create_cohn_certificate(&self, replace: bool) -> Result<(), Box<dyn Error>> {
+        cohn::create_certificate(self, replace).await
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Has the GoPro delete its COHN certificate
+    pub async fn clear_cohn_certificate(&self) -> Result<(), Box<dyn Error>> {
+        cohn::clear_certificate(self).await
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Returns the PEM encoded COHN certificate of the GoPro
+    pub async fn cohn_certificate(&self) -> Result<String, Box<dyn Error>> {
+        cohn::certificate(self).await
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Returns the COHN status of the GoPro
+    pub async fn cohn_status(&self) -> Result<CohnStatus, Box<dyn Error>> {
+        cohn::status(self).await
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Returns the credentials for making HTTPS requests to the GoPro on the home network
+    ///
+    ///The GoPro must have a COHN certificate (see [`GoPro::create_cohn_certificate`]) and
+    ///be connected to the network (see [`GoPro::connect_to_new_access_point`])
+    pub async fn cohn_credentials(&self) -> Result<CohnCredentials, Box<dyn Error>> {
+        cohn::credentials(self).await
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Has the GoPro scan for WiFi access points and waits for the scan to finish
+    ///
+    ///The access points found are listed with [`GoPro::access_points`]
+    pub async fn scan_access_points(&self) -> Result<AccessPointScan, Box<dyn Error>> {
+        network::scan(self).await
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Gets a page of the access points found by a scan
+    ///
+    /// # Arguments
+    /// * `scan` - The scan returned by [`GoPro::scan_access_points`]
+    /// * `start_index` - The index of the first access point to get
+    /// * `max_entries` - The most access points to get
+    pub async fn access_points(
+        &self,
+        scan: &AccessPointScan,
+        start_index: u32,
+        max_entries: u32,
+    ) -> Result<Vec<AccessPoint>, Box<dyn Error>> {
+        network::access_points(self, scan, start_index, max_entries).await
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Connects the GoPro to an access point it has been connected to before
+    ///
+    ///The outcome is reported by [`GoPro::provisioning_updates`], which should be
+    ///subscribed to before calling this
+    ///
+    /// # Arguments
+    /// * `ssid` - The name of the access point
+    pub async fn connect_to_access_point(
+        &self,
+        ssid: &str,
+    ) -> Result<AccessPointConnection, Box<dyn Error>> {
+        network::connect(self, ssid, None).await
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Connects the GoPro to an access point it hasn't been connected to before
+    ///
+    ///The outcome is reported by [`GoPro::provisioning_updates`], which should be
+    ///subscribed to before calling this
+    ///
+    /// # Arguments
+    /// * `ssid` - The name of the access point
+    /// * `password` - The password of the access point
+    pub async fn connect_to_new_access_point(
+        &self,
+        ssid: &str,
+        password: &str,
+    ) -> Result<AccessPointConnection, Box<dyn Error>> {
+        network::connect(self, ssid, Some(password)).await
+    }
+
+    #[cfg(feature = "wifi")]
+    ///Returns a stream of the provisioning states the GoPro reports while it
+    ///connects to an access point
+    pub async fn provisioning_updates(
+        &self,
+    ) -> Result<BoxStream<'static, ProvisioningState>, Box<dyn Error>> {
+        network::provisioning_updates(self).await
+    }
+
+    ///Returns the services discovered on the GoPro when it was connected, along with
+    ///their characteristics and the properties of each
+    ///
+    ///Useful for diagnosing differences between camera models and firmware versions
+    pub fn services(&self) -> BTreeSet<Service> {
+        self.device.services()
+    }
+
+    ///Returns the UUIDs of the characteristics that were subscribed to when the GoPro
+    ///was connected, those that can notify or indicate
+    pub fn subscribed_characteristics(&self) -> Vec<Uuid> {
+        self.device
+            .characteristics()
+            .iter()
+            .filter(|c| {
+                c.properties
+                    .intersects(CharPropFlags::NOTIFY | CharPropFlags::INDICATE)
+            })
+            .map(|c| c.uuid)
+            .collect()
     }
 
     ///Gets the next notification (response from a command) from the GoPro
@@ -208,8 +606,22 @@ impl GoPro {
         Ok(notification)
     }
 
+    ///Subscribes to the events emitted by the keep alive task
+    ///
+    /// # Returns
+    /// * `Some(Receiver)` - If the connection was made with a keep alive interval
+    /// * `None` - If keep alive is not enabled for this connection
+    pub fn keep_alive_events(&self) -> Option<broadcast::Receiver<KeepAliveEvent>> {
+        self.keep_alive.as_ref().map(|k| k.subscribe())
+    }
+
     ///Disconnects the GoPro
     pub async fn disconnect(self) -> Result<(), Box<dyn Error>> {
+        if let Some(keep_alive) = &self.keep_alive {
+            keep_alive.stop();
+        }
+        #[cfg(feature = "query")]
+        self.status_monitor.stop();
         self.device.disconnect().await?;
         Ok(())
     }
@@ -221,7 +633,12 @@ impl GoPro {
     /// The camera will continue to send advertisement packets for 10 hours after being powered off
     /// allowing for an auto wake on reconnecting
     pub async fn disconnect_and_poweroff(self) -> Result<(), Box<dyn Error>> {
+        if let Some(keep_alive) = &self.keep_alive {
+            keep_alive.stop();
+        }
         self.send_command(GoProCommand::Sleep.as_ref()).await?;
+        #[cfg(feature = "query")]
+        self.status_monitor.stop();
         self.device.disconnect().await?;
         Ok(())
     }
@@ -283,6 +700,25 @@ pub async fn connect(
     gopro_local_name: String,
     central: &mut Adapter,
 ) -> Result<GoPro, Box<dyn Error>> {
+    connect_with_options(gopro_local_name, central, ConnectOptions::default()).await
+}
+
+///Connects to a GoPro device by name with the given options and returns a GoPro
+///object if successful
+///
+/// # Arguments
+/// * `gopro_local_name` - The name of the GoPro device to connect to
+/// * `central` - The bluetooth adapter to use for connecting
+/// * `options` - Options controlling the behavior of the connection
+pub async fn connect_with_options(
+    gopro_local_name: String,
+    central: &mut Adapter,
+    options: ConnectOptions,
+) -> Result<GoPro, Box<dyn Error>> {
+    if options.keep_alive == Some(Duration::ZERO) {
+        return Err("Keep alive interval must be greater than zero".into());
+    }
+
     let device = filter_peripherals(central.peripherals().await?, gopro_local_name).await?;
     if device.is_none() {
         return Err("GoPro not found".into());
@@ -298,19 +734,144 @@ pub async fn connect(
     //subscribe to the proper notify characteristics
     let characteristics = device.characteristics();
 
-    if characteristics.len() == 0 {
+    if characteristics.is_empty() {
         return Err("No characteristics found on this GoPro".into());
     }
 
-    //Subscribe to all the characteristics that have the notify property
-    //TODO: Send off subscriptions concurently ?
-    for c in &characteristics {
-        if c.properties.bits() == CharPropFlags::NOTIFY.bits() {
-            device.subscribe(&c).await?;
-        }
+    //Subscribe to all the characteristics that can notify or indicate (whatever
+    //other properties they have) with the subscriptions sent off concurrently
+    let subscriptions = characteristics
+        .iter()
+        .filter(|c| {
+            c.properties
+                .intersects(CharPropFlags::NOTIFY | CharPropFlags::INDICATE)
+        })
+        .map(|c| device.subscribe(c));
+    futures::future::try_join_all(subscriptions).await?;
+
+    let characteristics = CharacteristicTable::resolve(&characteristics)?;
+
+    let exchange_lock = Arc::new(Mutex::new(()));
+    let keep_alive = options.keep_alive.map(|interval| {
+        KeepAlive::spawn(
+            device.clone(),
+            exchange_lock.clone(),
+            characteristics.settings.clone(),
+            interval,
+        )
+    });
+
+    #[cfg(feature = "query")]
+    let status_monitor = StatusMonitor::spawn(
+        device.clone(),
+        exchange_lock.clone(),
+        characteristics.query.clone(),
+    );
+
+    Ok(GoPro {
+        device,
+        characteristics,
+        exchange_lock,
+        keep_alive,
+        #[cfg(feature = "query")]
+        status_monitor,
+        #[cfg(feature = "query")]
+        wait_for_ready: options.wait_for_ready,
+    })
+}
+
+///Writes a message to a characteristic and waits for the GoPro to respond on another
+///
+///The exchange lock is held for the duration so that only one exchange is ever in flight.
+///Responses split over several packets are reassembled, and status updates that the
+///camera pushes while waiting for a query response are skipped
+///
+/// # Arguments
+/// * `device` - The GoPro peripheral
+/// * `exchange_lock` - The lock shared by everything talking to this GoPro
+/// * `write_char` - The characteristic to write to
+/// * `bytes` - The message to write
+/// * `response_uuid` - The UUID of the characteristic the response will arrive on
+pub(crate) async fn exchange(
+    device: &Peripheral,
+    exchange_lock: &Mutex<()>,
+    write_char: &Characteristic,
+    bytes: &[u8],
+    response_uuid: Uuid,
+) -> Result<ValueNotification, Box<dyn Error>> {
+    exchange_packets(
+        device,
+        exchange_lock,
+        write_char,
+        &[bytes],
+        response_uuid,
+        |_message| {
+            #[cfg(feature = "query")]
+            if packet::payload(_message)
+                .first()
+                .is_some_and(|id| query::ASYNC_QUERY_IDS.contains(id))
+            {
+                return false;
+            }
+            true
+        },
+    )
+    .await
+}
+
+///Writes the packets of a message to a characteristic and waits for the first complete
+///message on the response characteristic that `is_response` accepts
+///
+/// # Arguments
+/// * `device` - The GoPro peripheral
+/// * `exchange_lock` - The lock shared by everything talking to this GoPro
+/// * `write_char` - The characteristic to write to
+/// * `packets` - The packets making up the message, written in order
+/// * `response_uuid` - The UUID of the characteristic the response will arrive on
+/// * `is_response` - Returns true if a reassembled message (header included) is the response
+pub(crate) async fn exchange_packets<P: AsRef<[u8]>>(
+    device: &Peripheral,
+    exchange_lock: &Mutex<()>,
+    write_char: &Characteristic,
+    packets: &[P],
+    response_uuid: Uuid,
+    is_response: impl Fn(&[u8]) -> bool,
+) -> Result<ValueNotification, Box<dyn Error>> {
+    let _guard = exchange_lock.lock().await;
+
+    //Open the stream before writing so the response can't arrive before we're listening
+    let mut response_stream = device.notifications().await?;
+    for packet in packets {
+        device
+            .write(write_char, packet.as_ref(), WriteType::WithoutResponse)
+            .await?;
     }
 
-    Ok(GoPro { device })
+    let response = tokio::time::timeout(RESPONSE_TIMEOUT, async {
+        let mut accumulator = Accumulator::default();
+        while let Some(notification) = response_stream.next().await {
+            if notification.uuid != response_uuid {
+                continue;
+            }
+            let Some(message) = accumulator.push(&notification.value) else {
+                continue;
+            };
+            if !is_response(&message) {
+                continue;
+            }
+            return Some(ValueNotification {
+                uuid: notification.uuid,
+                value: message,
+            });
+        }
+        None
+    })
+    .await;
+
+    match response {
+        Ok(Some(notification)) => Ok(notification),
+        _ => Err("No response from GoPro".into()),
+    }
 }
 
 ///Filters a list of peripherals by name and returns the first one that matches
@@ -0,0 +1,457 @@
+//Typed model of the media stored on the camera, as listed by `/gopro/media/list`
+//
+//The format is described in the GoPro Open Spec:
+//<https://gopro.github.io/OpenGoPro/http#tag/Media/operation/OGP_MEDIA_LIST>
+
+use crate::http::GoProHttp;
+use serde::Deserialize;
+use serde_json::Value;
+use std::error::Error;
+use std::time::Duration;
+
+///A directory on the camera's SD card, e.g. `100GOPRO`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaDirectory {
+    pub name: String,
+    pub files: Vec<MediaFile>,
+}
+
+///A file on the camera's SD card
+///
+///Grouped media (bursts, time lapses, ...) is listed as a single file, the
+///first of the group, with the rest of the group described by [`MediaFile::group`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaFile {
+    ///The directory the file is in, e.g. `100GOPRO`
+    pub directory: String,
+    ///The name of the file, e.g. `GX010002.MP4`
+    pub name: String,
+    ///The size of the file in bytes, or of the whole group for grouped media
+    pub size: u64,
+    ///When the file was created, in seconds since the epoch in the camera's local time
+    pub created: u64,
+    ///When the file was last modified, in seconds since the epoch in the camera's local time
+    pub modified: u64,
+    pub group: Option<MediaGroup>,
+    ///The size of the low resolution proxy (LRV) of a video in bytes, if it has one
+    pub lrv_size: Option<u64>,
+}
+
+///The kind of capture a group of files came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaGroupKind {
+    Burst,
+    Continuous,
+    NightLapse,
+    TimeLapse,
+    ///A kind this crate doesn't know
+    Unknown,
+}
+
+///The files captured together as a burst, time lapse, ...
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaGroup {
+    pub id: u32,
+    pub kind: MediaGroupKind,
+    ///The number of the first file in the group
+    pub first: u32,
+    ///The number of the last file in the group
+    pub last: u32,
+    ///The numbers of the files between `first` and `last` that are no longer on the camera
+    pub missing: Vec<u32>,
+}
+
+impl MediaGroup {
+    ///Returns the numbers of the files in the group that are on the camera
+    pub fn members(&self) -> impl Iterator<Item = u32> + '_ {
+        (self.first..=self.last).filter(|n| !self.missing.contains(n))
+    }
+}
+
+impl MediaFile {
+    ///Returns the path of the file relative to the media root, e.g. `100GOPRO/GX010002.MP4`
+    pub fn path(&self) -> String {
+        format!("{}/{}", self.directory, self.name)
+    }
+
+    ///Returns the name of the low resolution proxy (LRV) of a video, e.g. `GL010002.LRV`
+    ///for `GX010002.MP4`, or None if it isn't a video with one
+    pub fn lrv_name(&self) -> Option<String> {
+        self.lrv_size?;
+        let stem = self.name.strip_suffix(".MP4")?;
+        //HEVC and AVC chapters share a prefix for their proxies, older names keep theirs
+        match stem.get(..2) {
+            Some("GX" | "GH") => Some(format!("GL{}.LRV", &stem[2..])),
+            _ => Some(format!("{stem}.LRV")),
+        }
+    }
+
+    ///Returns the names of every file on the camera that belongs to this one: the
+    ///members of its group, or just its own name for ungrouped media
+    ///
+    ///Group members share the name of the first file apart from its 4 digit file number,
+    ///e.g. `G0010003.JPG`, `G0010004.JPG`, ...
+    pub fn member_names(&self) -> Vec<String> {
+        let Some(group) = &self.group else {
+            return vec![self.name.clone()];
+        };
+        let (prefix, extension) = match (self.name.get(..4), self.name.get(8..)) {
+            (Some(prefix), Some(extension)) => (prefix, extension),
+            _ => return vec![self.name.clone()],
+        };
+        group
+            .members()
+            .map(|n| format!("{prefix}{n:04}{extension}"))
+            .collect()
+    }
+
+    ///Adds a hilight to the file on the camera
+    ///
+    /// # Arguments
+    /// * `http` - The camera the file is on
+    /// * `offset` - Where to add the hilight from the start of a video, which must lie
+    ///   inside it, or None to hilight a photo
+    pub async fn add_hilight(
+        &self,
+        http: &GoProHttp,
+        offset: Option<Duration>,
+    ) -> Result<(), Box<dyn Error>> {
+        let query = self.hilight_query(http, offset, false).await?;
+        http.get("/gopro/media/hilight/file", &query).await?;
+        Ok(())
+    }
+
+    ///Removes a hilight from the file on the camera
+    ///
+    /// # Arguments
+    /// * `http` - The camera the file is on
+    /// * `offset` - The hilight to remove from a video, as listed in
+    ///   [`MediaInfo::hilights`], or None for the hilight on a photo
+    pub async fn remove_hilight(
+        &self,
+        http: &GoProHttp,
+        offset: Option<Duration>,
+    ) -> Result<(), Box<dyn Error>> {
+        let query = self.hilight_query(http, offset, true).await?;
+        http.get("/gopro/media/hilight/remove", &query).await?;
+        Ok(())
+    }
+
+    ///Checks a hilight offset against the media info of the file and returns the query
+    ///for the hilight endpoints
+    async fn hilight_query(
+        &self,
+        http: &GoProHttp,
+        offset: Option<Duration>,
+        existing: bool,
+    ) -> Result<Vec<(&'static str, String)>, Box<dyn Error>> {
+        let info = http.media_info(self).await?;
+        let mut query = vec![("path", self.path())];
+        match (info.duration, offset) {
+            (Some(duration), Some(offset)) => {
+                //The camera reports whole seconds, so a video can be up to a second longer
+                if offset >= duration + Duration::from_secs(1) {
+                    return Err(format!(
+                        "{offset:?} is past the end of {}, which is {duration:?} long",
+                        self.name
+                    )
+                    .into());
+                }
+                let ms = offset.as_millis();
+                if existing && !info.hilights.iter().any(|h| h.as_millis() == ms) {
+                    return Err(format!("{} has no hilight at {offset:?}", self.name).into());
+                }
+                query.push(("ms", ms.to_string()));
+            }
+            (Some(_), None) => {
+                return Err(
+                    format!("{} is a video, so the hilight needs an offset", self.name).into(),
+                );
+            }
+            (None, Some(_)) => {
+                return Err(format!(
+                    "{} is a photo, so the hilight can't have an offset",
+                    self.name
+                )
+                .into());
+            }
+            (None, None) => {}
+        }
+        Ok(query)
+    }
+}
+
+///The media list as sent by the camera, where every number is a string
+#[derive(Deserialize)]
+struct RawMediaList {
+    media: Vec<RawDirectory>,
+}
+
+#[derive(Deserialize)]
+struct RawDirectory {
+    d: String,
+    fs: Vec<RawFile>,
+}
+
+#[derive(Deserialize)]
+struct RawFile {
+    n: String,
+    s: String,
+    cre: String,
+    #[serde(rename = "mod")]
+    modified: String,
+    g: Option<String>,
+    b: Option<String>,
+    l: Option<String>,
+    #[serde(default)]
+    m: Vec<String>,
+    t: Option<String>,
+    glrv: Option<String>,
+    ls: Option<String>,
+}
+
+///Parses a number the camera sent as a string
+fn number<T: std::str::FromStr>(field: &str, value: &str) -> Result<T, Box<dyn Error>> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid {field} in media list: {value:?}").into())
+}
+
+impl RawFile {
+    fn into_file(self, directory: &str) -> Result<MediaFile, Box<dyn Error>> {
+        let group = match (&self.g, &self.b, &self.l) {
+            (Some(id), Some(first), Some(last)) => Some(MediaGroup {
+                id: number("group id", id)?,
+                kind: match self.t.as_deref() {
+                    Some("b") => MediaGroupKind::Burst,
+                    Some("c") => MediaGroupKind::Continuous,
+                    Some("n") => MediaGroupKind::NightLapse,
+                    Some("t") => MediaGroupKind::TimeLapse,
+                    _ => MediaGroupKind::Unknown,
+                },
+                first: number("group start", first)?,
+                last: number("group end", last)?,
+                missing: self
+                    .m
+                    .iter()
+                    .map(|n| number("missing file", n))
+                    .collect::<Result<_, _>>()?,
+            }),
+            _ => None,
+        };
+        //`glrv` is the size of the proxy of a video and `ls` that of any low resolution
+        //file, which is -1 when there isn't one
+        let lrv_size = match (&self.glrv, &self.ls) {
+            (Some(size), _) => Some(number("proxy size", size)?),
+            (None, Some(size)) => number::<i64>("proxy size", size)?.try_into().ok(),
+            (None, None) => None,
+        };
+        Ok(MediaFile {
+            directory: directory.to_string(),
+            lrv_size,
+            size: number("size", &self.s)?,
+            created: number("creation time", &self.cre)?,
+            modified: number("modification time", &self.modified)?,
+            name: self.n,
+            group,
+        })
+    }
+}
+
+///Parses the JSON returned by `/gopro/media/list`
+pub(crate) fn parse_media_list(json: &str) -> Result<Vec<MediaDirectory>, Box<dyn Error>> {
+    let list: RawMediaList = serde_json::from_str(json)?;
+    list.media
+        .into_iter()
+        .map(|directory| {
+            let files = directory
+                .fs
+                .into_iter()
+                .map(|file| file.into_file(&directory.d))
+                .collect::<Result<_, _>>()?;
+            Ok(MediaDirectory {
+                name: directory.d,
+                files,
+            })
+        })
+        .collect()
+}
+
+///What the camera knows about a file, as returned by `/gopro/media/info`
+///
+///Fields that only apply to videos are None for photos
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaInfo {
+    ///The size of the file in bytes
+    pub size: u64,
+    ///When the file was created, in seconds since the epoch in the camera's local time
+    pub created: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    ///The frame rate of a video
+    pub fps: Option<f64>,
+    pub duration: Option<Duration>,
+    ///The offsets of the hilights in a video, or the hilight on a photo
+    pub hilights: Vec<Duration>,
+    ///Whether GPS data was recorded with the media
+    pub gps: bool,
+    ///The lens (field of view) the media was captured with, as the camera's code for it
+    pub lens: Option<String>,
+}
+
+///Reads a number from media info, which the camera sends as a string or a number
+fn info_number(info: &Value, key: &str) -> Option<u64> {
+    match &info[key] {
+        Value::String(s) => s.parse().ok(),
+        value => value.as_u64(),
+    }
+}
+
+///Parses the JSON returned by `/gopro/media/info`
+pub(crate) fn parse_media_info(json: &str) -> Result<MediaInfo, Box<dyn Error>> {
+    let info: Value = serde_json::from_str(json)?;
+    if !info.is_object() {
+        return Err("Invalid media info".into());
+    }
+    let fps = match (info_number(&info, "fps"), info_number(&info, "fps_denom")) {
+        (Some(fps), Some(denominator)) if denominator > 0 => Some(fps as f64 / denominator as f64),
+        (Some(fps), _) => Some(fps as f64),
+        _ => None,
+    };
+    let hilights = match &info["hi"] {
+        Value::Array(offsets) => offsets
+            .iter()
+            .filter_map(|offset| match offset {
+                Value::String(s) => s.parse().ok(),
+                offset => offset.as_u64(),
+            })
+            .map(Duration::from_millis)
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok(MediaInfo {
+        size: info_number(&info, "s").ok_or("Media info is missing the size")?,
+        created: info_number(&info, "cre").unwrap_or_default(),
+        width: info_number(&info, "w").and_then(|w| w.try_into().ok()),
+        height: info_number(&info, "h").and_then(|h| h.try_into().ok()),
+        fps,
+        duration: info_number(&info, "dur").map(Duration::from_secs),
+        hilights,
+        gps: info_number(&info, "gps") == Some(1),
+        lens: info["fov"].as_str().map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+pub(crate) const MEDIA_LIST_FIXTURE: &str = include_str!("../tests/fixtures/media_list.json");
+
+#[test]
+fn test_parse_media_list() {
+    let directories = parse_media_list(MEDIA_LIST_FIXTURE).unwrap();
+    assert_eq!(directories.len(), 2);
+    assert_eq!(directories[0].name, "100GOPRO");
+
+    let photo = &directories[0].files[0];
+    assert_eq!(photo.path(), "100GOPRO/GOPR0001.JPG");
+    assert_eq!(photo.size, 2806303);
+    assert_eq!(photo.created, 1696600109);
+    assert_eq!(photo.group, None);
+    assert_eq!(photo.member_names(), vec!["GOPR0001.JPG"]);
+
+    let video = &directories[0].files[1];
+    assert_eq!(video.modified, 1696600260);
+    assert_eq!(video.lrv_name().as_deref(), Some("GL010002.LRV"));
+    assert_eq!(photo.lrv_name(), None);
+
+    let burst = &directories[0].files[2];
+    let group = burst.group.as_ref().unwrap();
+    assert_eq!(group.kind, MediaGroupKind::Burst);
+    assert_eq!((group.id, group.first, group.last), (1, 3, 12));
+    assert_eq!(group.members().count(), 8);
+    let names = burst.member_names();
+    assert_eq!(names.first().unwrap(), "G0010003.JPG");
+    assert_eq!(names.last().unwrap(), "G0010012.JPG");
+    assert!(!names.contains(&"G0010007.JPG".to_string()));
+
+    let lapse = &directories[1].files[0];
+    assert_eq!(lapse.directory, "101GOPRO");
+    assert_eq!(
+        lapse.group.as_ref().unwrap().kind,
+        MediaGroupKind::TimeLapse
+    );
+    assert_eq!(lapse.member_names().len(), 4);
+
+    assert!(parse_media_list(
+        r#"{"media": [{"d": "100GOPRO", "fs": [{"n": "A", "s": "x", "cre": "1", "mod": "1"}]}]}"#
+    )
+    .is_err());
+}
+
+#[test]
+fn test_parse_media_info() {
+    let video = parse_media_info(include_str!("../tests/fixtures/media_info.json")).unwrap();
+    assert_eq!(video.size, 86724832);
+    assert_eq!((video.width, video.height), (Some(3840), Some(2160)));
+    assert_eq!(video.fps, Some(59.94005994005994));
+    assert_eq!(video.duration, Some(Duration::from_secs(12)));
+    assert_eq!(
+        video.hilights,
+        vec![Duration::from_millis(2002), Duration::from_millis(9510)]
+    );
+    assert!(video.gps);
+    assert_eq!(video.lens.as_deref(), Some("0"));
+
+    let photo = parse_media_info(r#"{"s": "2806303", "cre": "1696600109", "w": "5568"}"#).unwrap();
+    assert_eq!(photo.width, Some(5568));
+    assert_eq!((photo.fps, photo.duration), (None, None));
+    assert!(photo.hilights.is_empty() && !photo.gps);
+    assert!(parse_media_info("[]").is_err());
+}
+
+#[cfg(test)]
+use crate::mock_http::{self, Response};
+
+#[tokio::test]
+async fn test_hilights() {
+    let (base_url, log) = mock_http::serve(|request| match request.path.as_str() {
+        "/gopro/media/info?path=100GOPRO%2FGX010002.MP4" => {
+            Response::ok(r#"{"s": "400", "dur": "12", "hc": "1", "hi": [2002]}"#)
+        }
+        "/gopro/media/info?path=100GOPRO%2FGOPR0001.JPG" => Response::ok(r#"{"s": "5"}"#),
+        path if path.starts_with("/gopro/media/hilight/") => Response::ok("{}"),
+        _ => Response::status(404),
+    })
+    .await;
+    let http = GoProHttp::with_base_url(base_url).unwrap();
+    let directories = parse_media_list(MEDIA_LIST_FIXTURE).unwrap();
+    let (photo, video) = (&directories[0].files[0], &directories[0].files[1]);
+
+    let millis = |ms: u64| Some(Duration::from_millis(ms));
+    video.add_hilight(&http, millis(5500)).await.unwrap();
+    video.add_hilight(&http, millis(12900)).await.unwrap();
+    assert!(video.add_hilight(&http, millis(13000)).await.is_err());
+    assert!(video.add_hilight(&http, None).await.is_err());
+    video.remove_hilight(&http, millis(2002)).await.unwrap();
+    assert!(video.remove_hilight(&http, millis(3000)).await.is_err());
+    photo.add_hilight(&http, None).await.unwrap();
+    assert!(photo.add_hilight(&http, millis(1000)).await.is_err());
+    photo.remove_hilight(&http, None).await.unwrap();
+
+    let hilights: Vec<String> = log
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|r| r.path.starts_with("/gopro/media/hilight/"))
+        .map(|r| r.path.clone())
+        .collect();
+    assert_eq!(
+        hilights,
+        vec![
+            "/gopro/media/hilight/file?path=100GOPRO%2FGX010002.MP4&ms=5500",
+            "/gopro/media/hilight/file?path=100GOPRO%2FGX010002.MP4&ms=12900",
+            "/gopro/media/hilight/remove?path=100GOPRO%2FGX010002.MP4&ms=2002",
+            "/gopro/media/hilight/file?path=100GOPRO%2FGOPR0001.JPG",
+            "/gopro/media/hilight/remove?path=100GOPRO%2FGOPR0001.JPG"
+        ]
+    );
+}
@@ -0,0 +1,129 @@
+//A minimal HTTP server for testing the WiFi client without a camera
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+///A request received by the mock server
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    ///The path and query string, e.g. `/gopro/camera/setting?setting=2&option=1`
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    ///Returns the value of a header, whatever the case of its name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+///A response for the mock server to send
+pub(crate) struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    ///Drops the connection after sending this many bytes of the body, to
+    ///simulate the WiFi link going down midway through a response
+    pub truncate_after: Option<usize>,
+}
+
+impl Response {
+    ///A 200 response with the given body
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Response {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+            truncate_after: None,
+        }
+    }
+
+    ///An empty response with the given status
+    pub fn status(status: u16) -> Self {
+        Response {
+            status,
+            ..Response::ok("")
+        }
+    }
+
+    ///Adds a header to the response
+    pub fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+}
+
+///The requests the mock server has received
+pub(crate) type Log = Arc<Mutex<Vec<Request>>>;
+
+///Serves requests on a random local port until the test ends
+///
+/// # Returns
+/// The base URL of the server and a log of the requests it has received
+pub(crate) async fn serve(
+    handler: impl Fn(&Request) -> Response + Send + Sync + 'static,
+) -> (String, Log) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let handler = Arc::new(handler);
+
+    let task_log = log.clone();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let log = task_log.clone();
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let mut head = Vec::new();
+                let mut byte = [0u8; 1];
+                while !head.ends_with(b"\r\n\r\n") {
+                    if socket.read(&mut byte).await.unwrap_or(0) == 0 {
+                        return;
+                    }
+                    head.push(byte[0]);
+                }
+
+                let head = String::from_utf8_lossy(&head);
+                let mut lines = head.lines();
+                let path = lines
+                    .next()
+                    .and_then(|l| l.split_whitespace().nth(1))
+                    .unwrap_or("/")
+                    .to_string();
+                let headers = lines
+                    .filter_map(|l| l.split_once(':'))
+                    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                    .collect();
+                let request = Request { path, headers };
+
+                let response = handler(&request);
+                log.lock().unwrap().push(request);
+
+                let mut out = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    response.status,
+                    response.body.len()
+                );
+                for (name, value) in &response.headers {
+                    out.push_str(&format!("{name}: {value}\r\n"));
+                }
+                out.push_str("\r\n");
+
+                let _ = socket.write_all(out.as_bytes()).await;
+                let body = match response.truncate_after {
+                    Some(n) => &response.body[..n.min(response.body.len())],
+                    None => &response.body[..],
+                };
+                let _ = socket.write_all(body).await;
+                let _ = socket.shutdown().await;
+            });
+        }
+    });
+
+    (base_url, log)
+}
@@ -0,0 +1,1049 @@
+//Reading and losslessly concatenating MP4 files
+//
+//Only what GoPro chapters use of the ISO base media file format (ISO/IEC 14496-12)
+//is understood: the sample tables of every track and the hilights (HMMT) in the user
+//data are parsed, everything else is carried over as it is
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+///Boxes that only hold other boxes, which are parsed into a tree
+const CONTAINERS: [&[u8; 4]; 7] = [
+    b"moov", b"trak", b"edts", b"mdia", b"minf", b"dinf", b"stbl",
+];
+
+///The sample table boxes that are rebuilt when tracks are concatenated. The sample
+///groups and composition bounds are dropped, as they would no longer be right
+const SAMPLE_TABLE_BOXES: [&[u8; 4]; 12] = [
+    b"stts", b"ctts", b"stss", b"sdtp", b"stsc", b"stsz", b"stz2", b"stco", b"co64", b"sbgp",
+    b"sgpd", b"cslg",
+];
+
+///The largest moov box that will be read into memory
+const MAX_MOOV_SIZE: u64 = 256 * 1024 * 1024;
+
+///A box, with the boxes inside it parsed if it is a container
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Mp4Box {
+    Container {
+        kind: [u8; 4],
+        children: Vec<Mp4Box>,
+    },
+    Leaf {
+        kind: [u8; 4],
+        payload: Vec<u8>,
+    },
+}
+
+impl Mp4Box {
+    fn kind(&self) -> &[u8; 4] {
+        match self {
+            Mp4Box::Container { kind, .. } | Mp4Box::Leaf { kind, .. } => kind,
+        }
+    }
+
+    fn children(&self) -> &[Mp4Box] {
+        match self {
+            Mp4Box::Container { children, .. } => children,
+            Mp4Box::Leaf { .. } => &[],
+        }
+    }
+
+    fn payload(&self) -> Result<&[u8], Box<dyn Error>> {
+        match self {
+            Mp4Box::Leaf { payload, .. } => Ok(payload),
+            Mp4Box::Container { kind, .. } => {
+                Err(format!("{} isn't a leaf box", name(kind)).into())
+            }
+        }
+    }
+
+    ///Returns the first child box of a kind
+    fn child(&self, kind: &[u8; 4]) -> Option<&Mp4Box> {
+        self.children().iter().find(|b| b.kind() == kind)
+    }
+
+    fn child_mut(&mut self, kind: &[u8; 4]) -> Option<&mut Mp4Box> {
+        match self {
+            Mp4Box::Container { children, .. } => children.iter_mut().find(|b| b.kind() == kind),
+            Mp4Box::Leaf { .. } => None,
+        }
+    }
+
+    ///Returns the box at a path of kinds below this one, e.g. `[b"mdia", b"mdhd"]`
+    pub(crate) fn find(&self, path: &[&[u8; 4]]) -> Result<&Mp4Box, Box<dyn Error>> {
+        path.iter().try_fold(self, |parent, kind| {
+            parent
+                .child(kind)
+                .ok_or_else(|| format!("Missing {} box", name(kind)).into())
+        })
+    }
+
+    fn find_mut(&mut self, path: &[&[u8; 4]]) -> Result<&mut Mp4Box, Box<dyn Error>> {
+        path.iter().try_fold(self, |parent, kind| {
+            parent
+                .child_mut(kind)
+                .ok_or_else(|| format!("Missing {} box", name(kind)).into())
+        })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(self.kind());
+        match self {
+            Mp4Box::Container { children, .. } => children.iter().for_each(|c| c.write(out)),
+            Mp4Box::Leaf { payload, .. } => out.extend_from_slice(payload),
+        }
+        //Boxes too large for a 32 bit size are never written, as only moov is built here
+        let size = (out.len() - start) as u32;
+        out[start..start + 4].copy_from_slice(&size.to_be_bytes());
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write(&mut out);
+        out
+    }
+}
+
+///Returns the kind of a box as text for error messages
+fn name(kind: &[u8; 4]) -> String {
+    String::from_utf8_lossy(kind).into_owned()
+}
+
+///Parses the boxes laid out one after another in `data`
+pub(crate) fn parse_boxes(mut data: &[u8]) -> Result<Vec<Mp4Box>, Box<dyn Error>> {
+    let mut boxes = Vec::new();
+    while !data.is_empty() {
+        let mut reader = ByteReader::new(data);
+        let size = reader.u32()? as u64;
+        let kind: [u8; 4] = reader.bytes(4)?.try_into()?;
+        let (header, size) = match size {
+            0 => (8, data.len() as u64),
+            1 => (16, reader.u64()?),
+            size => (8, size),
+        };
+        if size < header || size > data.len() as u64 {
+            return Err(format!("Invalid size for {} box", name(&kind)).into());
+        }
+        let payload = &data[header as usize..size as usize];
+        boxes.push(match CONTAINERS.contains(&&kind) {
+            true => Mp4Box::Container {
+                kind,
+                children: parse_boxes(payload)?,
+            },
+            false => Mp4Box::Leaf {
+                kind,
+                payload: payload.to_vec(),
+            },
+        });
+        data = &data[size as usize..];
+    }
+    Ok(boxes)
+}
+
+///Reads big endian numbers from the payload of a box
+pub(crate) struct ByteReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        ByteReader { data }
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        if self.data.len() < len {
+            return Err("Unexpected end of data".into());
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Box<dyn Error>> {
+        Ok(u64::from_be_bytes(self.bytes(8)?.try_into()?))
+    }
+}
+
+///The top level of an MP4 file: its type, its metadata and where its media data is
+pub(crate) struct Mp4 {
+    ftyp: Option<Mp4Box>,
+    moov: Mp4Box,
+    ///The payloads of the mdat boxes, as positions in the file
+    mdats: Vec<Range<u64>>,
+}
+
+impl Mp4 {
+    ///Reads the metadata of an MP4 file, skipping over its media data
+    pub(crate) fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, Box<dyn Error>> {
+        let end = reader.seek(SeekFrom::End(0))?;
+        let mut position = reader.seek(SeekFrom::Start(0))?;
+        let (mut ftyp, mut moov, mut mdats) = (None, None, Vec::new());
+        while position < end {
+            let mut header = [0; 16];
+            reader.read_exact(&mut header[..8])?;
+            let kind: [u8; 4] = header[4..8].try_into()?;
+            let (header_len, size) = match u32::from_be_bytes(header[..4].try_into()?) {
+                0 => (8, end - position),
+                1 => {
+                    reader.read_exact(&mut header[8..])?;
+                    (16, u64::from_be_bytes(header[8..].try_into()?))
+                }
+                size => (8, size as u64),
+            };
+            if size < header_len
+                || position
+                    .checked_add(size)
+                    .is_none_or(|box_end| box_end > end)
+            {
+                return Err(format!("Invalid size for {} box", name(&kind)).into());
+            }
+            let payload = position + header_len..position + size;
+            match &kind {
+                b"mdat" => mdats.push(payload),
+                b"ftyp" | b"moov" => {
+                    if size > MAX_MOOV_SIZE {
+                        return Err(format!("The {} box is too large", name(&kind)).into());
+                    }
+                    let mut bytes = vec![0; (size - header_len) as usize];
+                    reader.read_exact(&mut bytes)?;
+                    let parsed = match &kind {
+                        b"moov" => Mp4Box::Container {
+                            kind,
+                            children: parse_boxes(&bytes)?,
+                        },
+                        _ => Mp4Box::Leaf {
+                            kind,
+                            payload: bytes,
+                        },
+                    };
+                    match &kind {
+                        b"moov" => moov = Some(parsed),
+                        _ => ftyp = Some(parsed),
+                    }
+                }
+                _ => {}
+            }
+            position = reader.seek(SeekFrom::Start(position + size))?;
+        }
+        Ok(Mp4 {
+            ftyp,
+            moov: moov.ok_or("Not an MP4 file, there is no moov box")?,
+            mdats,
+        })
+    }
+
+    ///Returns the trak boxes, in the order they are in the file
+    pub(crate) fn traks(&self) -> impl Iterator<Item = &Mp4Box> {
+        self.moov.children().iter().filter(|b| b.kind() == b"trak")
+    }
+
+    ///Returns the total size of the media data in bytes
+    pub(crate) fn media_len(&self) -> u64 {
+        self.mdats.iter().map(|range| range.end - range.start).sum()
+    }
+
+    ///Returns the duration of the movie in milliseconds
+    fn duration_millis(&self) -> Result<u64, Box<dyn Error>> {
+        let mvhd = self.moov.find(&[b"mvhd"])?;
+        Ok(duration(mvhd)? * 1000 / timescale(mvhd)?.max(1) as u64)
+    }
+
+    ///Returns the hilights in the HMMT box of the user data, in milliseconds from the
+    ///start of the file, or None if the file has no HMMT box
+    fn hilights(&self) -> Result<Option<Vec<u32>>, Box<dyn Error>> {
+        let Some(udta) = self.moov.child(b"udta") else {
+            return Ok(None);
+        };
+        let boxes = parse_boxes(udta.payload()?)?;
+        let Some(hmmt) = boxes.iter().find(|b| b.kind() == b"HMMT") else {
+            return Ok(None);
+        };
+        let mut reader = ByteReader::new(hmmt.payload()?);
+        let count = reader.u32()?;
+        (0..count)
+            .map(|_| reader.u32())
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+}
+
+///Replaces the hilights in the HMMT box of a moov box, keeping any room the camera
+///reserved for more
+fn set_hilights(moov: &mut Mp4Box, hilights: &[u32]) -> Result<(), Box<dyn Error>> {
+    let Some(Mp4Box::Leaf { payload: udta, .. }) = moov.child_mut(b"udta") else {
+        return Err("Missing udta box".into());
+    };
+    let mut boxes = parse_boxes(udta)?;
+    let Some(Mp4Box::Leaf { payload: hmmt, .. }) = boxes.iter_mut().find(|b| b.kind() == b"HMMT")
+    else {
+        return Err("Missing HMMT box".into());
+    };
+    let mut payload = (hilights.len() as u32).to_be_bytes().to_vec();
+    payload.extend(hilights.iter().flat_map(|h| h.to_be_bytes()));
+    if payload.len() < hmmt.len() {
+        payload.resize(hmmt.len(), 0);
+    }
+    *hmmt = payload;
+    *udta = boxes.iter().flat_map(Mp4Box::to_bytes).collect();
+    Ok(())
+}
+
+///The handler type of a track, e.g. `vide`, `soun` or `meta`
+pub(crate) fn handler(trak: &Mp4Box) -> Result<[u8; 4], Box<dyn Error>> {
+    let hdlr = trak.find(&[b"mdia", b"hdlr"])?.payload()?;
+    Ok(hdlr.get(8..12).ok_or("Truncated hdlr box")?.try_into()?)
+}
+
+///The kind of the first sample entry of a track, e.g. `avc1` or `gpmd`
+pub(crate) fn sample_entry(trak: &Mp4Box) -> Result<[u8; 4], Box<dyn Error>> {
+    let stsd = trak
+        .find(&[b"mdia", b"minf", b"stbl", b"stsd"])?
+        .payload()?;
+    Ok(stsd.get(12..16).ok_or("Truncated stsd box")?.try_into()?)
+}
+
+///Returns the position and width of the timescale in a mvhd or mdhd box, and of the
+///duration in those and tkhd boxes
+fn time_fields(kind: &[u8; 4], version: u8) -> (usize, (usize, usize)) {
+    match (kind, version) {
+        (b"tkhd", 1) => (0, (28, 8)),
+        (b"tkhd", _) => (0, (20, 4)),
+        (_, 1) => (20, (24, 8)),
+        _ => (12, (16, 4)),
+    }
+}
+
+///Reads the timescale of a mvhd or mdhd box
+pub(crate) fn timescale(header: &Mp4Box) -> Result<u32, Box<dyn Error>> {
+    let payload = header.payload()?;
+    let (at, _) = time_fields(header.kind(), *payload.first().ok_or("Empty box")?);
+    let bytes = payload.get(at..at + 4).ok_or("Truncated box")?;
+    Ok(u32::from_be_bytes(bytes.try_into()?))
+}
+
+///Reads the duration of a mvhd, mdhd or tkhd box
+fn duration(header: &Mp4Box) -> Result<u64, Box<dyn Error>> {
+    let payload = header.payload()?;
+    let (_, (at, len)) = time_fields(header.kind(), *payload.first().ok_or("Empty box")?);
+    let bytes = payload.get(at..at + len).ok_or("Truncated box")?;
+    Ok(bytes.iter().fold(0, |value, b| (value << 8) | *b as u64))
+}
+
+fn set_duration(header: &mut Mp4Box, duration: u64) -> Result<(), Box<dyn Error>> {
+    let kind = *header.kind();
+    let Mp4Box::Leaf { payload, .. } = header else {
+        return Err("Not a header box".into());
+    };
+    let (_, (at, len)) = time_fields(&kind, *payload.first().ok_or("Empty box")?);
+    if len == 4 && duration > u32::MAX as u64 {
+        return Err(format!("The duration is too long for the {} box", name(&kind)).into());
+    }
+    let bytes = duration.to_be_bytes();
+    payload
+        .get_mut(at..at + len)
+        .ok_or("Truncated box")?
+        .copy_from_slice(&bytes[8 - len..]);
+    Ok(())
+}
+
+///Where a sample is in the file and when it is presented, in the timescale of its track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Sample {
+    pub offset: u64,
+    pub size: u32,
+    ///The decoding time of the sample
+    pub time: u64,
+    pub duration: u32,
+}
+
+///The tables of a track that say where each sample is and when it is decoded
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SampleTable {
+    ///Runs of samples with the same duration, as (sample count, duration)
+    durations: Vec<(u32, u32)>,
+    ///The version of the ctts box and its runs of (sample count, composition offset)
+    composition_offsets: Option<(u8, Vec<(u32, u32)>)>,
+    ///The samples that are sync samples, numbered from 1. None if every sample is
+    sync_samples: Option<Vec<u32>>,
+    ///A byte of dependency flags per sample
+    dependencies: Option<Vec<u8>>,
+    ///Runs of chunks with the same number of samples, as (first chunk, samples per chunk,
+    ///sample description index)
+    chunks: Vec<(u32, u32, u32)>,
+    sizes: Vec<u32>,
+    chunk_offsets: Vec<u64>,
+}
+
+///The version of a full box and its entries, if the box is there
+type Entries<T> = Option<(u8, Vec<T>)>;
+
+///Reads the entries of a full box that starts with an entry count
+fn entries<T>(
+    stbl: &Mp4Box,
+    kind: &[u8; 4],
+    mut entry: impl FnMut(&mut ByteReader) -> Result<T, Box<dyn Error>>,
+) -> Result<Entries<T>, Box<dyn Error>> {
+    let Some(b) = stbl.child(kind) else {
+        return Ok(None);
+    };
+    let mut reader = ByteReader::new(b.payload()?);
+    let version = reader.u8()?;
+    reader.bytes(3)?;
+    let count = reader.u32()?;
+    let entries = (0..count)
+        .map(|_| entry(&mut reader))
+        .collect::<Result<_, _>>()?;
+    Ok(Some((version, entries)))
+}
+
+impl SampleTable {
+    ///Parses the sample table of a track
+    ///
+    /// # Arguments
+    /// * `trak` - The track
+    /// * `media_len` - The size of the media data of the file, which every sample must fit in
+    pub(crate) fn parse(trak: &Mp4Box, media_len: u64) -> Result<Self, Box<dyn Error>> {
+        let stbl = trak.find(&[b"mdia", b"minf", b"stbl"])?;
+        if stbl.child(b"stz2").is_some() {
+            return Err("Compact sample sizes (stz2) aren't supported".into());
+        }
+        let pair = |r: &mut ByteReader| Ok((r.u32()?, r.u32()?));
+
+        let mut stsz = ByteReader::new(stbl.find(&[b"stsz"])?.payload()?);
+        stsz.bytes(4)?;
+        let (sample_size, count) = (stsz.u32()?, stsz.u32()?);
+        let sizes = match sample_size {
+            0 => (0..count).map(|_| stsz.u32()).collect::<Result<_, _>>()?,
+            size if size as u64 * count as u64 > media_len => {
+                return Err("The stsz box holds more samples than the media data".into())
+            }
+            size => vec![size; count as usize],
+        };
+        let chunk_offsets = match entries(stbl, b"co64", |r| r.u64())? {
+            Some((_, offsets)) => offsets,
+            None => {
+                entries(stbl, b"stco", |r| Ok(r.u32()? as u64))?
+                    .ok_or("Missing stco box")?
+                    .1
+            }
+        };
+        let dependencies = match stbl.child(b"sdtp") {
+            Some(sdtp) => Some(sdtp.payload()?.get(4..).unwrap_or_default().to_vec()),
+            None => None,
+        };
+
+        Ok(SampleTable {
+            durations: entries(stbl, b"stts", pair)?.ok_or("Missing stts box")?.1,
+            composition_offsets: entries(stbl, b"ctts", pair)?,
+            sync_samples: entries(stbl, b"stss", |r| r.u32())?.map(|(_, s)| s),
+            dependencies,
+            chunks: entries(stbl, b"stsc", |r| Ok((r.u32()?, r.u32()?, r.u32()?)))?
+                .ok_or("Missing stsc box")?
+                .1,
+            sizes,
+            chunk_offsets,
+        })
+    }
+
+    pub(crate) fn sample_count(&self) -> u32 {
+        self.sizes.len() as u32
+    }
+
+    ///Returns where every sample is and when it is decoded
+    pub(crate) fn samples(&self) -> Result<Vec<Sample>, Box<dyn Error>> {
+        let mut durations = self
+            .durations
+            .iter()
+            .flat_map(|(count, duration)| (0..*count).map(move |_| *duration));
+        let mut sizes = self.sizes.iter();
+        let mut samples = Vec::with_capacity(self.sizes.len());
+        let mut time = 0;
+        for (index, run) in self.chunks.iter().enumerate() {
+            let last_chunk = match self.chunks.get(index + 1) {
+                Some(next) => next.0.checked_sub(1).ok_or("Invalid stsc box")?,
+                None => self.chunk_offsets.len() as u32,
+            };
+            for chunk in run.0..=last_chunk {
+                let index = (chunk as usize).checked_sub(1).ok_or("Invalid stsc box")?;
+                let mut offset = *self
+                    .chunk_offsets
+                    .get(index)
+                    .ok_or("The stsc box refers to a chunk that doesn't exist")?;
+                for _ in 0..run.1 {
+                    let size = *sizes
+                        .next()
+                        .ok_or("The stsc box holds more samples than stsz")?;
+                    let duration = durations.next().unwrap_or_default();
+                    samples.push(Sample {
+                        offset,
+                        size,
+                        time,
+                        duration,
+                    });
+                    offset = offset
+                        .checked_add(size as u64)
+                        .ok_or("A sample is past the end of the file")?;
+                    time += duration as u64;
+                }
+            }
+        }
+        Ok(samples)
+    }
+
+    ///Appends the samples of another table, whose chunk offsets are already where its
+    ///chunks will be in the output
+    fn append(&mut self, mut other: SampleTable) -> Result<(), Box<dyn Error>> {
+        let (samples, chunks) = (self.sample_count(), self.chunk_offsets.len() as u32);
+        self.durations.append(&mut other.durations);
+        self.composition_offsets =
+            match (self.composition_offsets.take(), other.composition_offsets) {
+                (Some((version, mut offsets)), Some((_, mut more))) => {
+                    offsets.append(&mut more);
+                    Some((version, offsets))
+                }
+                (None, None) => None,
+                _ => return Err("Only some chapters have composition offsets".into()),
+            };
+        //A missing stss means every sample is a sync sample
+        self.sync_samples = match (self.sync_samples.take(), other.sync_samples) {
+            (None, None) => None,
+            (mine, theirs) => {
+                let mut sync = mine.unwrap_or_else(|| (1..=samples).collect());
+                let theirs = theirs.unwrap_or_else(|| (1..=other.sizes.len() as u32).collect());
+                sync.extend(theirs.iter().map(|n| n + samples));
+                Some(sync)
+            }
+        };
+        self.dependencies = match (self.dependencies.take(), other.dependencies) {
+            (Some(mut mine), Some(mut theirs)) => {
+                mine.append(&mut theirs);
+                Some(mine)
+            }
+            _ => None,
+        };
+        self.chunks.extend(
+            other
+                .chunks
+                .iter()
+                .map(|(first, count, description)| (first + chunks, *count, *description)),
+        );
+        self.sizes.append(&mut other.sizes);
+        self.chunk_offsets.append(&mut other.chunk_offsets);
+        Ok(())
+    }
+
+    ///Returns the boxes holding the table, with 64 bit chunk offsets so their size
+    ///doesn't depend on where the chunks are
+    fn boxes(&self) -> Vec<Mp4Box> {
+        fn full_box(kind: &[u8; 4], version: u8, count: Option<usize>, body: Vec<u8>) -> Mp4Box {
+            let mut payload = vec![version, 0, 0, 0];
+            if let Some(count) = count {
+                payload.extend_from_slice(&(count as u32).to_be_bytes());
+            }
+            payload.extend(body);
+            Mp4Box::Leaf {
+                kind: *kind,
+                payload,
+            }
+        }
+        let pairs = |entries: &[(u32, u32)]| {
+            entries
+                .iter()
+                .flat_map(|(a, b)| [a.to_be_bytes(), b.to_be_bytes()].concat())
+                .collect()
+        };
+
+        let mut boxes = vec![full_box(
+            b"stts",
+            0,
+            Some(self.durations.len()),
+            pairs(&self.durations),
+        )];
+        if let Some((version, offsets)) = &self.composition_offsets {
+            boxes.push(full_box(
+                b"ctts",
+                *version,
+                Some(offsets.len()),
+                pairs(offsets),
+            ));
+        }
+        if let Some(sync) = &self.sync_samples {
+            let body = sync.iter().flat_map(|n| n.to_be_bytes()).collect();
+            boxes.push(full_box(b"stss", 0, Some(sync.len()), body));
+        }
+        if let Some(dependencies) = &self.dependencies {
+            boxes.push(full_box(b"sdtp", 0, None, dependencies.clone()));
+        }
+        let body = self
+            .chunks
+            .iter()
+            .flat_map(|(a, b, c)| [a.to_be_bytes(), b.to_be_bytes(), c.to_be_bytes()].concat())
+            .collect();
+        boxes.push(full_box(b"stsc", 0, Some(self.chunks.len()), body));
+        let mut body = vec![0; 4];
+        body.extend((self.sizes.len() as u32).to_be_bytes());
+        body.extend(self.sizes.iter().flat_map(|s| s.to_be_bytes()));
+        boxes.push(full_box(b"stsz", 0, None, body));
+        let body = self
+            .chunk_offsets
+            .iter()
+            .flat_map(|o| o.to_be_bytes())
+            .collect();
+        boxes.push(full_box(b"co64", 0, Some(self.chunk_offsets.len()), body));
+        boxes
+    }
+}
+
+///Sets the length of the edit list of a track, which must be a single edit as GoPro
+///writes them. Tracks with more edits lose their edit list
+fn set_edit_duration(trak: &mut Mp4Box, duration: u64) -> Result<(), Box<dyn Error>> {
+    let Ok(Mp4Box::Leaf { payload, .. }) = trak.find_mut(&[b"edts", b"elst"]) else {
+        return Ok(());
+    };
+    let mut reader = ByteReader::new(payload);
+    let version = reader.u8()?;
+    reader.bytes(3)?;
+    if reader.u32()? == 1 {
+        match version {
+            1 => payload[8..16].copy_from_slice(&duration.to_be_bytes()),
+            _ => match u32::try_from(duration) {
+                Ok(duration) => payload[8..12].copy_from_slice(&duration.to_be_bytes()),
+                Err(_) => return Err("The duration is too long for the elst box".into()),
+            },
+        }
+        return Ok(());
+    }
+    if let Mp4Box::Container { children, .. } = trak {
+        children.retain(|b| b.kind() != b"edts");
+    }
+    Ok(())
+}
+
+///Replaces the sample table boxes of a track, keeping its sample descriptions
+fn set_sample_table(trak: &mut Mp4Box, table: &SampleTable) -> Result<(), Box<dyn Error>> {
+    let Mp4Box::Container { children, .. } = trak.find_mut(&[b"mdia", b"minf", b"stbl"])? else {
+        return Err("Invalid stbl box".into());
+    };
+    let (mut kept, others): (Vec<_>, Vec<_>) = std::mem::take(children)
+        .into_iter()
+        .filter(|b| !SAMPLE_TABLE_BOXES.contains(&b.kind()))
+        .partition(|b| b.kind() == b"stsd");
+    kept.extend(table.boxes());
+    kept.extend(others);
+    *children = kept;
+    Ok(())
+}
+
+///A track of the output and its length so far, in the timescales of its mdhd and of the movie
+struct ConcatTrack {
+    table: SampleTable,
+    media_duration: u64,
+    movie_duration: u64,
+}
+
+///Concatenates MP4 files whose tracks match into one, without re-encoding them
+///
+///The metadata of the first file is kept, with the sample tables and durations of
+///every track and the hilights covering all of the files. The media data is copied as
+///it is
+pub(crate) fn concat<R: Read + Seek, W: Write>(
+    inputs: &mut [R],
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    let files: Vec<Mp4> = inputs
+        .iter_mut()
+        .map(|input| Mp4::read(input))
+        .collect::<Result<_, _>>()?;
+    let first = files.first().ok_or("There are no files to concatenate")?;
+
+    //Where the media data of each file goes in the output mdat
+    let mut layout: Vec<Vec<(Range<u64>, u64)>> = Vec::new();
+    let mut data_len = 0;
+    for file in &files {
+        layout.push(
+            file.mdats
+                .iter()
+                .map(|range| {
+                    let destination = data_len;
+                    data_len += range.end - range.start;
+                    (range.clone(), destination)
+                })
+                .collect(),
+        );
+    }
+
+    let movie_timescale = timescale(first.moov.find(&[b"mvhd"])?)?;
+    let mut movie_duration = 0;
+    //The hilights of every file from the start of the output, kept if the first has any
+    let mut hilights = first.hilights()?.map(|_| Vec::new());
+    let mut tracks: Vec<ConcatTrack> = Vec::new();
+    for (number, (file, layout)) in files.iter().zip(&layout).enumerate() {
+        let mvhd = file.moov.find(&[b"mvhd"])?;
+        if timescale(mvhd)? != movie_timescale {
+            return Err(
+                format!("File {} has a different timescale to the first", number + 1).into(),
+            );
+        }
+        if let Some(hilights) = &mut hilights {
+            let start: u32 = (movie_duration * 1000 / movie_timescale.max(1) as u64).try_into()?;
+            let file_hilights = file.hilights()?.unwrap_or_default();
+            hilights.extend(file_hilights.iter().map(|h| start.saturating_add(*h)));
+        }
+        movie_duration += duration(mvhd)?;
+
+        let traks: Vec<&Mp4Box> = file.traks().collect();
+        if traks.len() != first.traks().count() {
+            return Err(format!(
+                "File {} has a different number of tracks to the first",
+                number + 1
+            )
+            .into());
+        }
+        for (index, (trak, first_trak)) in traks.iter().zip(first.traks()).enumerate() {
+            let stsd = |t: &Mp4Box| {
+                Ok::<_, Box<dyn Error>>(
+                    t.find(&[b"mdia", b"minf", b"stbl", b"stsd"])?
+                        .payload()?
+                        .to_vec(),
+                )
+            };
+            let mdhd = trak.find(&[b"mdia", b"mdhd"])?;
+            if handler(trak)? != handler(first_trak)?
+                || stsd(trak)? != stsd(first_trak)?
+                || timescale(mdhd)? != timescale(first_trak.find(&[b"mdia", b"mdhd"])?)?
+            {
+                return Err(format!(
+                    "Track {} of file {} doesn't match the first file, they aren't chapters of one recording",
+                    index + 1,
+                    number + 1
+                )
+                .into());
+            }
+
+            let mut table = SampleTable::parse(trak, file.media_len())?;
+            for offset in &mut table.chunk_offsets {
+                let (range, destination) = layout
+                    .iter()
+                    .find(|(range, _)| range.contains(offset))
+                    .ok_or("A chunk is outside of the media data")?;
+                *offset = destination + (*offset - range.start);
+            }
+            let media_duration = duration(mdhd)?;
+            let track_duration = duration(trak.find(&[b"tkhd"])?)?;
+            match tracks.get_mut(index) {
+                Some(track) => {
+                    track.table.append(table)?;
+                    track.media_duration += media_duration;
+                    track.movie_duration += track_duration;
+                }
+                None => tracks.push(ConcatTrack {
+                    table,
+                    media_duration,
+                    movie_duration: track_duration,
+                }),
+            }
+        }
+    }
+
+    let ftyp = first
+        .ftyp
+        .as_ref()
+        .map(Mp4Box::to_bytes)
+        .unwrap_or_default();
+    let build_moov = |data_start: u64| -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut moov = first.moov.clone();
+        set_duration(moov.find_mut(&[b"mvhd"])?, movie_duration)?;
+        if let Some(hilights) = &hilights {
+            set_hilights(&mut moov, hilights)?;
+        }
+        let Mp4Box::Container { children, .. } = &mut moov else {
+            return Err("Invalid moov box".into());
+        };
+        let traks = children.iter_mut().filter(|b| b.kind() == b"trak");
+        for (trak, track) in traks.zip(&tracks) {
+            let mut table = track.table.clone();
+            table
+                .chunk_offsets
+                .iter_mut()
+                .for_each(|o| *o += data_start);
+            set_duration(trak.find_mut(&[b"tkhd"])?, track.movie_duration)?;
+            set_duration(trak.find_mut(&[b"mdia", b"mdhd"])?, track.media_duration)?;
+            set_edit_duration(trak, track.movie_duration)?;
+            set_sample_table(trak, &table)?;
+        }
+        Ok(moov.to_bytes())
+    };
+    //The size of the moov doesn't depend on the offsets in it, as they are all 64 bit
+    let mdat_header_len = 16;
+    let moov_len = build_moov(0)?.len() as u64;
+    let moov = build_moov(ftyp.len() as u64 + moov_len + mdat_header_len)?;
+
+    output.write_all(&ftyp)?;
+    output.write_all(&moov)?;
+    output.write_all(&1u32.to_be_bytes())?;
+    output.write_all(b"mdat")?;
+    output.write_all(&(mdat_header_len + data_len).to_be_bytes())?;
+    for (input, file) in inputs.iter_mut().zip(&files) {
+        for range in &file.mdats {
+            input.seek(SeekFrom::Start(range.start))?;
+            let len = range.end - range.start;
+            if io::copy(&mut input.by_ref().take(len), output)? != len {
+                return Err("A file ended before its media data".into());
+            }
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+///Joins the chapters of a recording into a single MP4 without re-encoding them
+///
+///The chapters must be in the order they were recorded, e.g. as in
+///`Recording::chapters`, and come from the same recording so that their tracks
+///match. Every track is kept, including the GPMF telemetry, as are the hilights of
+///every chapter. The media data is streamed so chapters are never held in memory
+///
+/// # Arguments
+/// * `chapters` - The paths of the downloaded chapters
+/// * `output` - Where to write the joined file, which is removed again if joining fails
+pub fn concat_chapters(
+    chapters: &[impl AsRef<Path>],
+    output: impl AsRef<Path>,
+) -> Result<(), Box<dyn Error>> {
+    let mut inputs = chapters
+        .iter()
+        .map(|path| Ok(BufReader::new(File::open(path)?)))
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+    let output = output.as_ref();
+    let result = concat(&mut inputs, &mut BufWriter::new(File::create(output)?));
+    if result.is_err() {
+        let _ = std::fs::remove_file(output);
+    }
+    result
+}
+
+///Reads the hilights a GoPro wrote to the HMMT box of each chapter of a recording
+///
+///Returns the position of every hilight from the start of the recording, with those
+///of later chapters offset by the duration of the chapters before them. Chapters
+///without an HMMT box have no hilights
+///
+/// # Arguments
+/// * `chapters` - The paths of the downloaded chapters in the order they were recorded,
+///   or a single file such as chapters joined by [`concat_chapters`]
+pub fn read_hilights(chapters: &[impl AsRef<Path>]) -> Result<Vec<Duration>, Box<dyn Error>> {
+    let (mut hilights, mut start) = (Vec::new(), 0);
+    for path in chapters {
+        let mp4 = Mp4::read(&mut BufReader::new(File::open(path)?))?;
+        let offsets = mp4.hilights()?.unwrap_or_default();
+        hilights.extend(
+            offsets
+                .iter()
+                .map(|h| Duration::from_millis(start + *h as u64)),
+        );
+        start += mp4.duration_millis()?;
+    }
+    Ok(hilights)
+}
+
+#[cfg(test)]
+pub(crate) const CHAPTERS: [&[u8]; 2] = [
+    include_bytes!("../tests/fixtures/GX010001.MP4"),
+    include_bytes!("../tests/fixtures/GX020001.MP4"),
+];
+
+///Returns the bytes of every sample of a track
+#[cfg(test)]
+fn sample_bytes(file: &[u8], trak: &Mp4Box) -> Vec<Vec<u8>> {
+    SampleTable::parse(trak, file.len() as u64)
+        .unwrap()
+        .samples()
+        .unwrap()
+        .iter()
+        .map(|s| file[s.offset as usize..s.offset as usize + s.size as usize].to_vec())
+        .collect()
+}
+
+#[test]
+fn test_read_samples() {
+    let mp4 = Mp4::read(&mut io::Cursor::new(CHAPTERS[0])).unwrap();
+    let traks: Vec<&Mp4Box> = mp4.traks().collect();
+    assert_eq!(handler(traks[0]).unwrap(), *b"vide");
+    assert_eq!(sample_entry(traks[1]).unwrap(), *b"gpmd");
+
+    let video = SampleTable::parse(traks[0], mp4.media_len())
+        .unwrap()
+        .samples()
+        .unwrap();
+    assert_eq!(video.len(), 3);
+    assert_eq!(video[2].time, 60000);
+    let frames = sample_bytes(CHAPTERS[0], traks[0]);
+    assert_eq!(frames[0], b"VIDEO 1.0 ".repeat(4));
+    assert_eq!(frames[2], b"VIDEO 1.2 ".repeat(2));
+    assert!(sample_bytes(CHAPTERS[0], traks[1])[0].starts_with(b"DEVC"));
+}
+
+#[test]
+fn test_concat() {
+    let mut inputs: Vec<_> = CHAPTERS.iter().map(|c| io::Cursor::new(*c)).collect();
+    let mut output = Vec::new();
+    concat(&mut inputs, &mut output).unwrap();
+
+    let joined = Mp4::read(&mut io::Cursor::new(&output)).unwrap();
+    let chapters: Vec<Mp4> = CHAPTERS
+        .iter()
+        .map(|c| Mp4::read(&mut io::Cursor::new(*c)).unwrap())
+        .collect();
+    assert_eq!(joined.mdats.len(), 1);
+    assert_eq!(
+        duration(joined.moov.find(&[b"mvhd"]).unwrap()).unwrap(),
+        1667
+    );
+    //The firmware version of the first chapter is kept, with the hilights of both
+    assert_eq!(chapters[1].hilights().unwrap(), Some(vec![300]));
+    assert_eq!(joined.hilights().unwrap(), Some(vec![250, 800, 1300]));
+    let udta = |mp4: &Mp4| mp4.moov.child(b"udta").unwrap().payload().unwrap().to_vec();
+    assert_eq!(udta(&joined).len(), udta(&chapters[0]).len());
+    assert!(udta(&joined).starts_with(&parse_boxes(&udta(&chapters[0])).unwrap()[0].to_bytes()));
+
+    let traks: Vec<&Mp4Box> = joined.traks().collect();
+    assert_eq!(traks.len(), 2);
+    for (index, trak) in traks.iter().enumerate() {
+        //Every sample of every chapter is in the output, in order
+        let expected: Vec<Vec<u8>> = CHAPTERS
+            .iter()
+            .zip(&chapters)
+            .flat_map(|(bytes, c)| sample_bytes(bytes, c.traks().nth(index).unwrap()))
+            .collect();
+        assert_eq!(sample_bytes(&output, trak), expected);
+        assert_eq!(duration(trak.find(&[b"tkhd"]).unwrap()).unwrap(), 1667);
+    }
+
+    let video = SampleTable::parse(traks[0], joined.media_len()).unwrap();
+    assert_eq!(video.sample_count(), 5);
+    assert_eq!(video.sync_samples, Some(vec![1, 4]));
+    assert_eq!(
+        video.chunks,
+        vec![(1, 2, 1), (2, 1, 1), (3, 1, 1), (4, 1, 1)]
+    );
+    assert_eq!(video.composition_offsets, Some((0, vec![(3, 0), (2, 0)])));
+    let samples = video.samples().unwrap();
+    assert_eq!(samples[4].time, 4 * 30000);
+    assert_eq!(
+        duration(traks[0].find(&[b"mdia", b"mdhd"]).unwrap()).unwrap(),
+        150000
+    );
+    let elst = traks[0]
+        .find(&[b"edts", b"elst"])
+        .unwrap()
+        .payload()
+        .unwrap();
+    assert_eq!(elst[8..12], 1667u32.to_be_bytes());
+
+    let telemetry = SampleTable::parse(traks[1], joined.media_len())
+        .unwrap()
+        .samples()
+        .unwrap();
+    assert_eq!(telemetry.len(), 2);
+    assert_eq!(telemetry[1].time, 1000);
+}
+
+#[test]
+fn test_concat_mismatched() {
+    let mut inputs = vec![
+        io::Cursor::new(CHAPTERS[0]),
+        io::Cursor::new(&b"not an mp4"[..]),
+    ];
+    assert!(concat(&mut inputs, &mut Vec::new()).is_err());
+
+    //A chapter with its telemetry track removed
+    let mut moov = Mp4::read(&mut io::Cursor::new(CHAPTERS[1])).unwrap().moov;
+    if let Mp4Box::Container { children, .. } = &mut moov {
+        let last_trak = children.iter().rposition(|b| b.kind() == b"trak").unwrap();
+        children.remove(last_trak);
+    }
+    let mut file = Mp4Box::Leaf {
+        kind: *b"ftyp",
+        payload: b"mp41".to_vec(),
+    }
+    .to_bytes();
+    file.extend(moov.to_bytes());
+    let mut inputs = vec![io::Cursor::new(CHAPTERS[0].to_vec()), io::Cursor::new(file)];
+    assert!(concat(&mut inputs, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn test_read_hilights() {
+    let chapters = ["GX010001.MP4", "GX020001.MP4"].map(|name| {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    });
+    let millis = |ms: &[u64]| {
+        ms.iter()
+            .map(|ms| Duration::from_millis(*ms))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(read_hilights(&chapters[1..]).unwrap(), millis(&[300]));
+    assert_eq!(read_hilights(&chapters).unwrap(), millis(&[250, 800, 1300]));
+
+    let joined = std::env::temp_dir().join(format!("gopro-hilights-{}.MP4", std::process::id()));
+    concat_chapters(&chapters, &joined).unwrap();
+    assert_eq!(
+        read_hilights(&[&joined]).unwrap(),
+        millis(&[250, 800, 1300])
+    );
+    std::fs::remove_file(&joined).unwrap();
+    assert!(read_hilights(&["missing.MP4"]).is_err());
+}
+
+#[test]
+fn test_corrupt_tables() {
+    let mp4 = Mp4::read(&mut io::Cursor::new(CHAPTERS[0])).unwrap();
+    let with_box = |kind: &[u8; 4], payload: Vec<u8>| {
+        let mut trak = mp4.traks().next().unwrap().clone();
+        let table = trak.find_mut(&[b"mdia", b"minf", b"stbl"]).unwrap();
+        *table.child_mut(kind).unwrap() = Mp4Box::Leaf {
+            kind: *kind,
+            payload,
+        };
+        trak
+    };
+
+    //A constant sample size with far more samples than the file could hold
+    let stsz = [[0; 4], 1u32.to_be_bytes(), u32::MAX.to_be_bytes()].concat();
+    assert!(SampleTable::parse(&with_box(b"stsz", stsz), mp4.media_len()).is_err());
+
+    //Chunks numbered from 0 rather than 1
+    let stsc = [
+        [0; 4],
+        1u32.to_be_bytes(),
+        [0; 4],
+        3u32.to_be_bytes(),
+        1u32.to_be_bytes(),
+    ];
+    let table = SampleTable::parse(&with_box(b"stsc", stsc.concat()), mp4.media_len()).unwrap();
+    assert!(table.samples().is_err());
+
+    //A 64 bit box size that overflows the position of the end of the box
+    let mut file = CHAPTERS[0].to_vec();
+    file.extend([0, 0, 0, 1]);
+    file.extend(b"free");
+    file.extend(u64::MAX.to_be_bytes());
+    assert!(Mp4::read(&mut io::Cursor::new(file)).is_err());
+}
@@ -0,0 +1,464 @@
+use crate::protobuf;
+use crate::services::{GoProManagementCharacteristics as GPMC, ToUUID};
+use crate::GoPro;
+use btleplug::api::{Peripheral as _, ValueNotification};
+use futures::stream::{BoxStream, Stream, StreamExt};
+use prost::Message;
+use std::error::Error;
+use std::time::Duration;
+
+//The feature and action IDs of the network management protobuf messages
+//
+//NOTE: The IDs and message definitions were taken directly from the GoPro Open Spec:
+//<https://gopro.github.io/OpenGoPro/ble_2_0#protobuf>
+const NETWORK_MANAGEMENT_FEATURE_ID: u8 = 0x02;
+const START_SCAN_ID: u8 = 0x02;
+const START_SCAN_RESPONSE_ID: u8 = 0x82;
+const SCAN_NOTIFICATION_ID: u8 = 0x0B;
+const GET_AP_ENTRIES_ID: u8 = 0x03;
+const GET_AP_ENTRIES_RESPONSE_ID: u8 = 0x83;
+const CONNECT_ID: u8 = 0x04;
+const CONNECT_RESPONSE_ID: u8 = 0x84;
+const CONNECT_NEW_ID: u8 = 0x05;
+const CONNECT_NEW_RESPONSE_ID: u8 = 0x85;
+const PROVISIONING_NOTIFICATION_ID: u8 = 0x0C;
+
+///How long to wait for the camera to finish scanning for access points
+const SCAN_TIMEOUT: Duration = Duration::from_secs(60);
+
+///The progress of a scan for access points
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum ScanState {
+    Unknown = 0,
+    NeverStarted = 1,
+    Started = 2,
+    AbortedBySystem = 3,
+    CancelledByUser = 4,
+    Success = 5,
+}
+
+///The progress of connecting the camera to an access point
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum ProvisioningState {
+    Unknown = 0,
+    NeverStarted = 1,
+    Started = 2,
+    AbortedBySystem = 3,
+    CancelledByUser = 4,
+    SuccessNewAp = 5,
+    SuccessOldAp = 6,
+    ErrorFailedToAssociate = 7,
+    ErrorPasswordAuth = 8,
+    ErrorEulaBlocking = 9,
+    ErrorNoInternet = 10,
+    ErrorUnsupportedType = 11,
+}
+
+impl ProvisioningState {
+    ///Returns true if the camera has finished trying to connect, successfully or not
+    pub fn is_finished(&self) -> bool {
+        !matches!(
+            self,
+            ProvisioningState::Unknown
+                | ProvisioningState::NeverStarted
+                | ProvisioningState::Started
+        )
+    }
+
+    ///Returns true if the camera is connected to the access point
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self,
+            ProvisioningState::SuccessNewAp | ProvisioningState::SuccessOldAp
+        )
+    }
+}
+
+///A finished scan for access points, whose results can be listed with [`GoPro::access_points`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessPointScan {
+    ///Identifies the scan when listing its results
+    pub scan_id: i32,
+    ///How many access points were found
+    pub total_entries: u32,
+    ///How many access points the camera has been connected to before
+    pub total_configured: u32,
+}
+
+///An access point found by a scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessPoint {
+    pub ssid: String,
+    ///Signal strength from 0 to 4 bars
+    pub signal_bars: i32,
+    pub frequency_mhz: i32,
+    ///True if a password is needed to join the access point
+    pub secured: bool,
+    ///True if the camera has been connected to the access point before, in which case
+    ///it can be joined again with [`GoPro::connect_to_access_point`]
+    pub configured: bool,
+    ///True if the camera is currently connected to the access point
+    pub associated: bool,
+    ///True if the access point uses a security type the camera doesn't support
+    pub unsupported: bool,
+}
+
+///The camera's answer to a request to connect to an access point
+///
+///Connecting continues after this is received, the outcome is reported
+///by the updates from [`GoPro::provisioning_updates`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessPointConnection {
+    pub state: ProvisioningState,
+    ///How long the camera may take to finish connecting
+    pub timeout: Duration,
+}
+
+///The protobuf messages exchanged with the network management feature
+mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct RequestStartScan {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct ResponseStartScanning {
+        #[prost(enumeration = "crate::protobuf::ResultGeneric", required, tag = "1")]
+        pub result: i32,
+        #[prost(enumeration = "super::ScanState", required, tag = "2")]
+        pub scanning_state: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct NotifStartScanning {
+        #[prost(enumeration = "super::ScanState", required, tag = "1")]
+        pub scanning_state: i32,
+        #[prost(int32, optional, tag = "2")]
+        pub scan_id: Option<i32>,
+        #[prost(int32, optional, tag = "3")]
+        pub total_entries: Option<i32>,
+        #[prost(int32, required, tag = "4")]
+        pub total_configured_ssid: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct RequestGetApEntries {
+        #[prost(int32, required, tag = "1")]
+        pub start_index: i32,
+        #[prost(int32, required, tag = "2")]
+        pub max_entries: i32,
+        #[prost(int32, required, tag = "3")]
+        pub scan_id: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct ScanEntry {
+        #[prost(string, required, tag = "1")]
+        pub ssid: String,
+        #[prost(int32, required, tag = "2")]
+        pub signal_strength_bars: i32,
+        #[prost(int32, required, tag = "4")]
+        pub signal_frequency_mhz: i32,
+        #[prost(int32, required, tag = "5")]
+        pub scan_entry_flags: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct ResponseGetApEntries {
+        #[prost(enumeration = "crate::protobuf::ResultGeneric", required, tag = "1")]
+        pub result: i32,
+        #[prost(int32, required, tag = "2")]
+        pub scan_id: i32,
+        #[prost(message, repeated, tag = "3")]
+        pub entries: Vec<ScanEntry>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct RequestConnect {
+        #[prost(string, required, tag = "1")]
+        pub ssid: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct RequestConnectNew {
+        #[prost(string, required, tag = "1")]
+        pub ssid: String,
+        #[prost(string, required, tag = "2")]
+        pub password: String,
+    }
+
+    ///Shared by the responses to RequestConnect and RequestConnectNew
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct ResponseConnect {
+        #[prost(enumeration = "crate::protobuf::ResultGeneric", required, tag = "1")]
+        pub result: i32,
+        #[prost(enumeration = "super::ProvisioningState", required, tag = "2")]
+        pub provisioning_state: i32,
+        #[prost(int32, required, tag = "3")]
+        pub timeout_seconds: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct NotifProvisioningState {
+        #[prost(enumeration = "super::ProvisioningState", required, tag = "1")]
+        pub provisioning_state: i32,
+    }
+}
+
+//Flags describing a scan entry
+const SCAN_FLAG_AUTHENTICATED: i32 = 0x01;
+const SCAN_FLAG_CONFIGURED: i32 = 0x02;
+const SCAN_FLAG_ASSOCIATED: i32 = 0x08;
+const SCAN_FLAG_UNSUPPORTED_TYPE: i32 = 0x10;
+
+impl From<proto::ScanEntry> for AccessPoint {
+    fn from(entry: proto::ScanEntry) -> Self {
+        let flag = |f: i32| entry.scan_entry_flags & f != 0;
+        AccessPoint {
+            secured: flag(SCAN_FLAG_AUTHENTICATED),
+            configured: flag(SCAN_FLAG_CONFIGURED),
+            associated: flag(SCAN_FLAG_ASSOCIATED),
+            unsupported: flag(SCAN_FLAG_UNSUPPORTED_TYPE),
+            ssid: entry.ssid,
+            signal_bars: entry.signal_strength_bars,
+            frequency_mhz: entry.signal_frequency_mhz,
+        }
+    }
+}
+
+///Sends a network management request and decodes the response
+///
+/// # Arguments
+/// * `gopro` - The GoPro to send the request to
+/// * `action_id` - The action ID of the request
+/// * `request` - The request message
+/// * `response_id` - The action ID of the expected response
+async fn request<R: Message + Default>(
+    gopro: &GoPro,
+    action_id: u8,
+    request: &impl Message,
+    response_id: u8,
+) -> Result<R, Box<dyn Error>> {
+    let write_char = gopro
+        .characteristics
+        .network_management
+        .as_ref()
+        .ok_or("Network management is not supported by this GoPro")?;
+
+    protobuf::request(
+        gopro,
+        write_char,
+        GPMC::NetworkManagementResponse.to_uuid(),
+        [NETWORK_MANAGEMENT_FEATURE_ID, action_id],
+        request,
+        [NETWORK_MANAGEMENT_FEATURE_ID, response_id],
+    )
+    .await
+}
+
+///Decodes the network management notifications with the given action ID
+///from a stream of notifications from the camera
+fn notifications<M: Message + Default + 'static>(
+    notifications: impl Stream<Item = ValueNotification> + Send + 'static,
+    action_id: u8,
+) -> impl Stream<Item = M> + Send + 'static {
+    protobuf::notifications(
+        notifications,
+        GPMC::NetworkManagementResponse.to_uuid(),
+        [NETWORK_MANAGEMENT_FEATURE_ID, action_id],
+    )
+}
+
+///Starts a scan for access points and waits for it to finish
+pub(crate) async fn scan(gopro: &GoPro) -> Result<AccessPointScan, Box<dyn Error>> {
+    //Listen before starting so the scan finishing can't be missed
+    let mut updates = Box::pin(notifications::<proto::NotifStartScanning>(
+        gopro.device.notifications().await?,
+        SCAN_NOTIFICATION_ID,
+    ));
+
+    let response: proto::ResponseStartScanning = request(
+        gopro,
+        START_SCAN_ID,
+        &proto::RequestStartScan {},
+        START_SCAN_RESPONSE_ID,
+    )
+    .await?;
+    protobuf::check_result(response.result())?;
+
+    let finished = tokio::time::timeout(SCAN_TIMEOUT, async {
+        while let Some(update) = updates.next().await {
+            if update.scanning_state() != ScanState::Started {
+                return Some(update);
+            }
+        }
+        None
+    })
+    .await;
+
+    match finished {
+        Ok(Some(update)) if update.scanning_state() == ScanState::Success => Ok(AccessPointScan {
+            scan_id: update.scan_id.ok_or("GoPro didn't report a scan ID")?,
+            total_entries: update.total_entries.unwrap_or(0).max(0) as u32,
+            total_configured: update.total_configured_ssid.max(0) as u32,
+        }),
+        Ok(Some(update)) => Err(format!("Scan failed: {:?}", update.scanning_state()).into()),
+        Ok(None) => Err("Notifications from GoPro ended during the scan".into()),
+        Err(_) => Err("Timed out waiting for the scan to finish".into()),
+    }
+}
+
+///Gets a page of the access points found by a scan
+pub(crate) async fn access_points(
+    gopro: &GoPro,
+    scan: &AccessPointScan,
+    start_index: u32,
+    max_entries: u32,
+) -> Result<Vec<AccessPoint>, Box<dyn Error>> {
+    let response: proto::ResponseGetApEntries = request(
+        gopro,
+        GET_AP_ENTRIES_ID,
+        &proto::RequestGetApEntries {
+            start_index: start_index.try_into()?,
+            max_entries: max_entries.try_into()?,
+            scan_id: scan.scan_id,
+        },
+        GET_AP_ENTRIES_RESPONSE_ID,
+    )
+    .await?;
+    protobuf::check_result(response.result())?;
+    Ok(response
+        .entries
+        .into_iter()
+        .map(AccessPoint::from)
+        .collect())
+}
+
+///Asks the camera to connect to an access point, with a password if it is a new one
+pub(crate) async fn connect(
+    gopro: &GoPro,
+    ssid: &str,
+    password: Option<&str>,
+) -> Result<AccessPointConnection, Box<dyn Error>> {
+    let response: proto::ResponseConnect = match password {
+        Some(password) => {
+            let req = proto::RequestConnectNew {
+                ssid: ssid.to_string(),
+                password: password.to_string(),
+            };
+            request(gopro, CONNECT_NEW_ID, &req, CONNECT_NEW_RESPONSE_ID).await?
+        }
+        None => {
+            let req = proto::RequestConnect {
+                ssid: ssid.to_string(),
+            };
+            request(gopro, CONNECT_ID, &req, CONNECT_RESPONSE_ID).await?
+        }
+    };
+    protobuf::check_result(response.result())?;
+    Ok(AccessPointConnection {
+        state: response.provisioning_state(),
+        timeout: Duration::from_secs(response.timeout_seconds.max(0) as u64),
+    })
+}
+
+///Returns the provisioning states the camera reports while connecting to an access point
+pub(crate) async fn provisioning_updates(
+    gopro: &GoPro,
+) -> Result<BoxStream<'static, ProvisioningState>, Box<dyn Error>> {
+    let updates = notifications::<proto::NotifProvisioningState>(
+        gopro.device.notifications().await?,
+        PROVISIONING_NOTIFICATION_ID,
+    );
+    Ok(updates.map(|n| n.provisioning_state()).boxed())
+}
+
+#[cfg(test)]
+fn notification(action_id: u8, message: &impl Message) -> Vec<ValueNotification> {
+    protobuf::test_notifications(
+        GPMC::NetworkManagementResponse.to_uuid(),
+        [NETWORK_MANAGEMENT_FEATURE_ID, action_id],
+        message,
+    )
+}
+
+#[tokio::test]
+async fn test_provisioning_notifications() {
+    let state = |s: ProvisioningState| proto::NotifProvisioningState {
+        provisioning_state: s as i32,
+    };
+    let mut pushed = notification(
+        PROVISIONING_NOTIFICATION_ID,
+        &state(ProvisioningState::Started),
+    );
+    // Scan notifications share the characteristic and must be skipped
+    pushed.extend(notification(
+        SCAN_NOTIFICATION_ID,
+        &proto::NotifStartScanning {
+            scanning_state: ScanState::Success as i32,
+            scan_id: Some(1),
+            total_entries: Some(2),
+            total_configured_ssid: 0,
+        },
+    ));
+    pushed.extend(notification(
+        PROVISIONING_NOTIFICATION_ID,
+        &state(ProvisioningState::SuccessNewAp),
+    ));
+
+    let states: Vec<ProvisioningState> = notifications::<proto::NotifProvisioningState>(
+        futures::stream::iter(pushed),
+        PROVISIONING_NOTIFICATION_ID,
+    )
+    .map(|n| n.provisioning_state())
+    .collect()
+    .await;
+    assert_eq!(
+        states,
+        vec![ProvisioningState::Started, ProvisioningState::SuccessNewAp]
+    );
+    assert!(!states[0].is_finished());
+    assert!(states[1].is_finished() && states[1].is_success());
+}
+
+#[test]
+fn test_decode_ap_entries() {
+    let response = proto::ResponseGetApEntries {
+        result: protobuf::ResultGeneric::Success as i32,
+        scan_id: 7,
+        entries: vec![
+            proto::ScanEntry {
+                ssid: "production-lan-with-a-long-name".into(),
+                signal_strength_bars: 3,
+                signal_frequency_mhz: 5180,
+                scan_entry_flags: SCAN_FLAG_AUTHENTICATED | SCAN_FLAG_CONFIGURED,
+            },
+            proto::ScanEntry {
+                ssid: "guest".into(),
+                signal_strength_bars: 1,
+                signal_frequency_mhz: 2412,
+                scan_entry_flags: 0,
+            },
+        ],
+    };
+
+    // Long enough to be split over several packets
+    let packets = notification(GET_AP_ENTRIES_RESPONSE_ID, &response);
+    assert!(packets.len() > 1);
+    let mut acc = crate::packet::Accumulator::default();
+    let message = packets.iter().find_map(|p| acc.push(&p.value)).unwrap();
+    assert!(protobuf::is_message(
+        &message,
+        [NETWORK_MANAGEMENT_FEATURE_ID, GET_AP_ENTRIES_RESPONSE_ID]
+    ));
+
+    let decoded: proto::ResponseGetApEntries = protobuf::decode(&message).unwrap();
+    assert!(protobuf::check_result(decoded.result()).is_ok());
+    let access_points: Vec<AccessPoint> =
+        decoded.entries.into_iter().map(AccessPoint::from).collect();
+    assert_eq!(access_points[0].ssid, "production-lan-with-a-long-name");
+    assert!(access_points[0].secured && access_points[0].configured);
+    assert!(!access_points[0].associated);
+    assert_eq!(access_points[1].frequency_mhz, 2412);
+    assert!(!access_points[1].secured);
+}
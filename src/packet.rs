@@ -0,0 +1,174 @@
+//Helpers for the BLE packet headers used by the GoPro
+//
+//Messages longer than a single BLE packet are split into a start packet and
+//continuation packets as described in the GoPro Open Spec:
+//<https://gopro.github.io/OpenGoPro/ble_2_0#packet-headers>
+
+///Returns the length of the header and the length of the message
+///described by the first packet of a message, or None if the packet
+///is a continuation packet or is malformed
+pub(crate) fn start_header(packet: &[u8]) -> Option<(usize, usize)> {
+    let first = *packet.first()?;
+    match first >> 5 {
+        // General (5 bit length)
+        0b000 => Some((1, (first & 0x1F) as usize)),
+        // Extended (13 bit length)
+        0b001 => {
+            let second = *packet.get(1)?;
+            Some((2, (((first & 0x1F) as usize) << 8) | second as usize))
+        }
+        // Extended (16 bit length)
+        0b010 => {
+            let length = u16::from_be_bytes([*packet.get(1)?, *packet.get(2)?]);
+            Some((3, length as usize))
+        }
+        _ => None,
+    }
+}
+
+///Returns true if the packet continues a message started by a previous packet
+pub(crate) fn is_continuation(packet: &[u8]) -> bool {
+    packet.first().is_some_and(|b| b & 0x80 != 0)
+}
+
+///Returns the message payload (everything after the header) of a complete message
+#[cfg(feature = "query")]
+pub(crate) fn payload(message: &[u8]) -> &[u8] {
+    match start_header(message) {
+        Some((header_len, _)) => &message[header_len.min(message.len())..],
+        None => &[],
+    }
+}
+
+///The largest packet that can be written to the GoPro
+#[cfg(feature = "wifi")]
+const MAX_PACKET_LEN: usize = 20;
+
+///The longest message payload a start packet header can describe
+#[cfg(feature = "wifi")]
+const MAX_MESSAGE_LEN: usize = 0xFFFF;
+
+///Splits a message payload into packets, the first starting with a header
+///describing the length of the message and the rest with continuation headers
+///
+///Returns an error if the payload is longer than a header can describe
+#[cfg(feature = "wifi")]
+pub(crate) fn fragment(payload: &[u8]) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    let length = payload.len();
+    let header = match length {
+        0..=0x1F => vec![length as u8],
+        0x20..=0x1FFF => vec![0x20 | (length >> 8) as u8, length as u8],
+        0x2000..=MAX_MESSAGE_LEN => {
+            let length = (length as u16).to_be_bytes();
+            vec![0x40, length[0], length[1]]
+        }
+        _ => {
+            return Err(format!(
+                "The message is {length} bytes long, more than the {MAX_MESSAGE_LEN} a BLE message can hold"
+            )
+            .into())
+        }
+    };
+
+    let (first, mut rest) = payload.split_at(length.min(MAX_PACKET_LEN - header.len()));
+    let mut packets = vec![[header.as_slice(), first].concat()];
+    let mut counter = 0u8;
+    while !rest.is_empty() {
+        let (next, remaining) = rest.split_at(rest.len().min(MAX_PACKET_LEN - 1));
+        packets.push([&[0x80 | (counter & 0x0F)], next].concat());
+        counter = counter.wrapping_add(1);
+        rest = remaining;
+    }
+    Ok(packets)
+}
+
+///Reassembles messages that have been split over multiple packets
+///
+///The messages produced keep the header of the start packet, so for
+///messages that fit in a single packet they are identical to the packet
+#[derive(Default)]
+pub(crate) struct Accumulator {
+    message: Vec<u8>,
+    expected: usize,
+}
+
+impl Accumulator {
+    ///Adds a packet to the message being built
+    ///
+    /// # Returns
+    /// * `Some(Vec<u8>)` - The complete message if this packet finished it
+    /// * `None` - If more packets are needed (or the packet was out of place)
+    pub(crate) fn push(&mut self, packet: &[u8]) -> Option<Vec<u8>> {
+        if is_continuation(packet) {
+            if self.message.is_empty() {
+                // A continuation of a message we never saw the start of
+                return None;
+            }
+            self.message.extend_from_slice(&packet[1..]);
+        } else {
+            let (header_len, length) = start_header(packet)?;
+            self.message = packet.to_vec();
+            self.expected = header_len + length;
+        }
+
+        if self.message.len() >= self.expected {
+            self.message.truncate(self.expected);
+            self.expected = 0;
+            return Some(std::mem::take(&mut self.message));
+        }
+        None
+    }
+}
+
+#[test]
+fn test_accumulator() {
+    let mut acc = Accumulator::default();
+    // Single packet messages come straight back out
+    assert_eq!(acc.push(&[0x02, 0x01, 0x00]), Some(vec![0x02, 0x01, 0x00]));
+
+    // A 13 bit header message split over two packets
+    let first = [0x20, 0x04, 0x13, 0x00, 0x01];
+    let second = [0x80, 0x01];
+    assert_eq!(acc.push(&first), None);
+    assert_eq!(
+        acc.push(&second),
+        Some(vec![0x20, 0x04, 0x13, 0x00, 0x01, 0x01])
+    );
+    #[cfg(feature = "query")]
+    assert_eq!(
+        payload(&[0x20, 0x04, 0x13, 0x00, 0x01, 0x01]),
+        &[0x13, 0x00, 0x01, 0x01]
+    );
+
+    // Stray continuation packets are ignored
+    assert_eq!(acc.push(&[0x81, 0x01]), None);
+}
+
+#[cfg(feature = "wifi")]
+#[test]
+fn test_fragment() {
+    assert_eq!(
+        fragment(&[0x02, 0x02]).unwrap(),
+        vec![vec![0x02, 0x02, 0x02]]
+    );
+
+    // 40 bytes needs a 13 bit header and two continuation packets
+    let bytes: Vec<u8> = (0..40).collect();
+    let packets = fragment(&bytes).unwrap();
+    assert_eq!(packets.len(), 3);
+    assert_eq!(&packets[0][..2], &[0x20, 40]);
+    assert_eq!(packets[0].len(), 20);
+    assert_eq!(packets[1][0], 0x80);
+    assert_eq!(packets[2][0], 0x81);
+    assert!(packets.iter().all(|p| p.len() <= MAX_PACKET_LEN));
+
+    // Fragmenting then reassembling gives back the message
+    let mut acc = Accumulator::default();
+    let message = packets.iter().find_map(|p| acc.push(p)).unwrap();
+    assert_eq!(payload(&message), bytes.as_slice());
+
+    // The longest message a 16 bit header can describe, and one byte too many
+    let packets = fragment(&vec![0; 0xFFFF]).unwrap();
+    assert_eq!(&packets[0][..3], &[0x40, 0xFF, 0xFF]);
+    assert!(fragment(&vec![0; 0x10000]).is_err());
+}
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+///The preset group (reported by [`crate::query::StatusID::ActivePresetsGroup`])
+///that the camera is in when it is in photo mode
+///
+///NOTE: The ID was taken directly from the GoPro Open Spec:
+///<https://gopro.github.io/OpenGoPro/ble_2_0#commands-quick-reference>
+pub(crate) const PHOTO_PRESET_GROUP: u64 = 1001;
+
+///How long to wait for the camera to start capturing after the shutter is fired
+pub(crate) const CAPTURE_START_TIMEOUT: Duration = Duration::from_secs(2);
+
+///How long to wait for a capture to finish, long enough for night photos
+pub(crate) const CAPTURE_TIMEOUT: Duration = Duration::from_secs(60);
+
+///The storage counts the camera reported after a photo was taken with [`crate::GoPro::take_photo`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhotoCapture {
+    ///How many more photos fit on the sdcard
+    pub remaining_photos: u64,
+    ///How many photos are on the sdcard, including the one just taken
+    pub total_photos: u64,
+}
@@ -0,0 +1,132 @@
+//Helpers for the protobuf messages used by newer GoPro features
+//
+//Each message is preceded by a feature ID and an action ID identifying it, and is
+//split into packets like any other message, as described in the GoPro Open Spec:
+//<https://gopro.github.io/OpenGoPro/ble_2_0#protobuf>
+
+use crate::packet::{self, Accumulator};
+use crate::GoPro;
+use btleplug::api::{Characteristic, ValueNotification};
+use futures::stream::{Stream, StreamExt};
+use prost::Message;
+use std::error::Error;
+use uuid::Uuid;
+
+///The result most protobuf responses start with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum ResultGeneric {
+    Unknown = 0,
+    Success = 1,
+    IllFormed = 2,
+    NotSupported = 3,
+    ArgumentOutOfBounds = 4,
+    ArgumentInvalid = 5,
+    ResourceNotAvailable = 6,
+}
+
+///The response to requests that only report whether they succeeded
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ResponseGeneric {
+    #[prost(enumeration = "ResultGeneric", required, tag = "1")]
+    pub result: i32,
+}
+
+///Returns an error unless the camera reported that a request succeeded
+pub(crate) fn check_result(result: ResultGeneric) -> Result<(), Box<dyn Error>> {
+    match result {
+        ResultGeneric::Success => Ok(()),
+        failure => Err(format!("GoPro rejected the request: {:?}", failure).into()),
+    }
+}
+
+///Returns the payload of a message: the feature and action IDs followed by the encoded message
+///
+/// # Arguments
+/// * `id` - The feature ID and action ID of the message
+/// * `message` - The message to encode
+pub(crate) fn encode(id: [u8; 2], message: &impl Message) -> Vec<u8> {
+    [id.as_slice(), &message.encode_to_vec()].concat()
+}
+
+///Returns true if a complete message (header included) has the given feature and action IDs
+pub(crate) fn is_message(message: &[u8], id: [u8; 2]) -> bool {
+    packet::payload(message).starts_with(&id)
+}
+
+///Decodes the protobuf message held by a complete message (header included)
+pub(crate) fn decode<M: Message + Default>(message: &[u8]) -> Result<M, Box<dyn Error>> {
+    let body = packet::payload(message)
+        .get(2..)
+        .ok_or("Protobuf message from GoPro was too short")?;
+    Ok(M::decode(body)?)
+}
+
+///Sends a protobuf request and decodes the response
+///
+/// # Arguments
+/// * `gopro` - The GoPro to send the request to
+/// * `write_char` - The characteristic to write the request to
+/// * `response_uuid` - The UUID of the characteristic the response will arrive on
+/// * `id` - The feature ID and action ID of the request
+/// * `request` - The request message
+/// * `response_id` - The feature ID and action ID of the expected response
+pub(crate) async fn request<R: Message + Default>(
+    gopro: &GoPro,
+    write_char: &Characteristic,
+    response_uuid: Uuid,
+    id: [u8; 2],
+    request: &impl Message,
+    response_id: [u8; 2],
+) -> Result<R, Box<dyn Error>> {
+    let res = crate::exchange_packets(
+        &gopro.device,
+        &gopro.exchange_lock,
+        write_char,
+        &packet::fragment(&encode(id, request))?,
+        response_uuid,
+        |message| is_message(message, response_id),
+    )
+    .await?;
+    decode(&res.value)
+}
+
+///Decodes the protobuf notifications with the given feature and action IDs
+///from a stream of notifications from the camera
+///
+/// # Arguments
+/// * `notifications` - The notifications from the camera
+/// * `uuid` - The UUID of the characteristic the notifications arrive on
+/// * `id` - The feature ID and action ID of the notifications
+pub(crate) fn notifications<M: Message + Default + 'static>(
+    notifications: impl Stream<Item = ValueNotification> + Send + 'static,
+    uuid: Uuid,
+    id: [u8; 2],
+) -> impl Stream<Item = M> + Send + 'static {
+    notifications
+        .filter(move |n| futures::future::ready(n.uuid == uuid))
+        .scan(Accumulator::default(), |accumulator, n| {
+            futures::future::ready(Some(accumulator.push(&n.value)))
+        })
+        .filter_map(move |message| {
+            futures::future::ready(
+                message
+                    .filter(|m| is_message(m, id))
+                    .and_then(|m| decode(&m).ok()),
+            )
+        })
+}
+
+///Builds the notifications the camera would send for a message, for tests
+#[cfg(test)]
+pub(crate) fn test_notifications(
+    uuid: Uuid,
+    id: [u8; 2],
+    message: &impl Message,
+) -> Vec<ValueNotification> {
+    packet::fragment(&encode(id, message))
+        .unwrap()
+        .into_iter()
+        .map(|value| ValueNotification { uuid, value })
+        .collect()
+}
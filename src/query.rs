@@ -108,7 +108,7 @@ fn query_builder(query_id: u8, status_ids: Vec<StatusID>) -> Vec<u8> {
 ///
 /// The integers in this implementation were taken directly from the GoPro Open Spec:
 /// <https://gopro.github.io/OpenGoPro/ble_2_0#status-ids>
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum StatusID {
     /// Indicates if the system’s internal battery is present.
     InternalBatteryPresent = 1,
@@ -479,7 +479,7 @@ pub struct QueryResponse {
 }
 
 /// Represents the different ways that a query response can be interpreted
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum QueryResponseIntepretation {
     /// The response was a single byte
     Byte(u8),
@@ -494,6 +494,35 @@ pub enum QueryResponseIntepretation {
     String(String),
 }
 
+impl QueryResponseIntepretation {
+    /// Returns the value as an unsigned integer where that makes sense
+    ///
+    /// # Note:
+    /// Integer statuses that don't fit in a byte are sent as big endian ByteVecs
+    ///
+    /// # Returns:
+    /// * `Some(u64)` - For Byte, Percentage, Bool and ByteVec values of at most 8 bytes
+    /// * `None` - For anything else
+    pub fn as_integer(&self) -> Option<u64> {
+        match self {
+            QRI::Byte(b) | QRI::Percentage(b) => Some(*b as u64),
+            QRI::Bool(b) => Some(*b as u64),
+            QRI::ByteVec(bytes) if bytes.len() <= 8 => Some(
+                bytes
+                    .iter()
+                    .fold(0u64, |value, byte| (value << 8) | *byte as u64),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// The query id the camera uses when it pushes a registered status value update
+pub(crate) const ASYNC_STATUS_UPDATE_ID: u8 = 0x93;
+
+/// Query ids that the camera sends on its own rather than in response to a query
+pub(crate) const ASYNC_QUERY_IDS: [u8; 3] = [0x92, ASYNC_STATUS_UPDATE_ID, 0xA2];
+
 use QueryResponseIntepretation as QRI;
 use StatusID as S;
 impl QueryResponse {
@@ -542,6 +571,45 @@ impl QueryResponse {
         })
     }
 
+    /// Deserializes a complete message payload (without its packet header) which may
+    /// hold several status or setting values into one QueryResponse per value
+    ///
+    /// # Note:
+    /// Registration responses and pushed status updates usually carry more than one
+    /// value, and are often longer than a single BLE packet so should be reassembled first
+    pub fn deserialize_many(payload: &[u8]) -> Result<Vec<Self>, &'static str> {
+        if payload.len() < 2 {
+            return Err("Data too short for header");
+        }
+        let query_id = payload[0];
+        let command_status = payload[1];
+
+        let mut responses = Vec::new();
+        let mut index = 2;
+        while index < payload.len() {
+            if payload.len() < index + 2 {
+                return Err("Data too short for status header");
+            }
+            let status_id = payload[index];
+            let status_value_length = payload[index + 1];
+            let status_value_start = index + 2;
+            let status_value_end = status_value_start + status_value_length as usize;
+            if payload.len() < status_value_end {
+                return Err("Data too short for status value");
+            }
+            responses.push(QueryResponse {
+                message_length: payload.len() as u16,
+                query_id,
+                command_status,
+                status_id,
+                status_value_length,
+                status_value: payload[status_value_start..status_value_end].to_vec(),
+            });
+            index = status_value_end;
+        }
+        Ok(responses)
+    }
+
     /// If the caller is only interested in the status value, this function
     /// will return it in a format that is easier to work with
     ///
@@ -558,14 +626,58 @@ impl QueryResponse {
     ///
     /// # Returns:
     /// * `Some(QueryResponseIntepretation)` - If the status id is valid
-    /// * `None` - If the status id is invalid
+    /// * `None` - If the status id is invalid or its value is malformed
     pub fn interpret(&self) -> Option<QueryResponseIntepretation> {
-        let status_id = StatusID::try_from(self.status_id);
-        if let Err(_) = status_id {
-            return None;
-        }
-        let status_id = status_id.unwrap();
-        let interpretation = match status_id {
+        let status_id = StatusID::try_from(self.status_id).ok()?;
+        let interpretation = match status_id.kind() {
+            StatusKind::Bool => QRI::Bool(*self.status_value.first()? == 1),
+            StatusKind::Byte => QRI::Byte(*self.status_value.first()?),
+            StatusKind::ByteVec => QRI::ByteVec(self.status_value.to_vec()),
+            StatusKind::String => QRI::String(String::from_utf8(self.status_value.to_vec()).ok()?),
+            StatusKind::Percentage => QRI::Percentage(*self.status_value.first()?),
+        };
+        Some(interpretation)
+    }
+}
+
+/// The shape of the value a status holds, which decides how it is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StatusKind {
+    Bool,
+    Byte,
+    ByteVec,
+    String,
+    Percentage,
+}
+
+impl StatusID {
+    /// Interprets a status value that was reported as a number rather than as
+    /// bytes, as the HTTP API reports them
+    ///
+    /// # Note:
+    /// ByteVec values are given as 4 big endian bytes (8 if the value needs them)
+    ///
+    /// # Returns:
+    /// * `Some(QueryResponseIntepretation)` - If the status holds a number
+    /// * `None` - If the status holds a string
+    #[cfg(feature = "wifi")]
+    pub(crate) fn interpret_integer(&self, value: u64) -> Option<QueryResponseIntepretation> {
+        let interpretation = match self.kind() {
+            StatusKind::Bool => QRI::Bool(value == 1),
+            StatusKind::Byte => QRI::Byte(value as u8),
+            StatusKind::Percentage => QRI::Percentage(value as u8),
+            StatusKind::ByteVec => match u32::try_from(value) {
+                Ok(value) => QRI::ByteVec(value.to_be_bytes().to_vec()),
+                Err(_) => QRI::ByteVec(value.to_be_bytes().to_vec()),
+            },
+            StatusKind::String => return None,
+        };
+        Some(interpretation)
+    }
+
+    /// Returns the shape of the value the status holds
+    pub(crate) fn kind(&self) -> StatusKind {
+        match self {
             //Group Bool Like Responses
             S::InternalBatteryPresent
             | S::SystemHot
@@ -596,7 +708,7 @@ impl QueryResponse {
             | S::SdRatingCheckError
             | S::TurboTransfer
             | S::UsbConnected
-            | S::AllowControlOverUsb => QRI::Bool(self.status_value[0] == 1),
+            | S::AllowControlOverUsb => StatusKind::Bool,
 
             // Group Byte Like Responses
             S::InternalBatteryLevel
@@ -614,7 +726,7 @@ impl QueryResponse {
             | S::TimewarpSpeedRampActive
             | S::CameraLensType
             | S::MediaModStatus
-            | S::CameraControlStatus => QRI::Byte(self.status_value[0]),
+            | S::CameraControlStatus => StatusKind::Byte,
 
             // Group ByteVec Like Responses
             S::VideoProgressCounter
@@ -647,23 +759,44 @@ impl QueryResponse {
             | S::NumTotalLiveBursts
             | S::ScheduledPreset
             | S::SdWriteSpeedError
-            | S::TotalSDSpaceKB => QRI::ByteVec(self.status_value.to_vec()),
+            | S::TotalSDSpaceKB => StatusKind::ByteVec,
 
             //Group String Like Responses
-            S::WlanSSID | S::ApSSID => {
-                QRI::String(String::from_utf8(self.status_value.to_vec()).unwrap())
-            }
+            S::WlanSSID | S::ApSSID => StatusKind::String,
 
             //Group Percentage Like Responses
             S::ExposureSelectX
             | S::ExposureSelectY
             | S::InternalBatteryPercentage
-            | S::DigitalZoom => QRI::Percentage(self.status_value[0]),
-        };
-        Some(interpretation)
+            | S::DigitalZoom => StatusKind::Percentage,
+        }
     }
 }
 
+#[test]
+fn test_deserialize_many() {
+    // A pushed update for SystemBusy (8) and EncodingActive (10)
+    let responses =
+        QueryResponse::deserialize_many(&[0x93, 0x00, 0x08, 0x01, 0x01, 0x0A, 0x01, 0x00]).unwrap();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0].interpret(), Some(QRI::Bool(true)));
+    assert_eq!(responses[1].status_id, 0x0A);
+    assert_eq!(responses[1].interpret(), Some(QRI::Bool(false)));
+
+    assert!(QueryResponse::deserialize_many(&[0x93, 0x00, 0x08, 0x04, 0x01]).is_err());
+}
+
+#[test]
+fn test_interpret_malformed() {
+    // An empty SystemBusy (8), an empty InternalBatteryPercentage (70) and an ApSSID (30) that isn't UTF-8
+    let responses = QueryResponse::deserialize_many(&[
+        0x93, 0x00, 0x08, 0x00, 0x46, 0x00, 0x1E, 0x02, 0xC3, 0x28,
+    ])
+    .unwrap();
+    assert_eq!(responses.len(), 3);
+    assert!(responses.iter().all(|r| r.interpret().is_none()));
+}
+
 #[test]
 /// Sanity test for query builder
 fn test_query_builder() {
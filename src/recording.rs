@@ -0,0 +1,79 @@
+use crate::query::StatusID;
+use crate::services::{GoProControlAndQueryCharacteristics as GPCharac, Sendable, ToUUID};
+use crate::{GoPro, GoProCommand};
+use std::error::Error;
+use std::time::Duration;
+
+///How long [`RecordingSession::stop`] waits for the camera to finish encoding
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+///A video recording in progress, started with [`GoPro::start_recording`]
+///
+/// # Note
+/// If the session is dropped without [`RecordingSession::stop`] having succeeded
+/// (for example when returning early on an error) the shutter is stopped in the background
+pub struct RecordingSession<'a> {
+    gopro: &'a GoPro,
+    stopped: bool,
+}
+
+impl<'a> RecordingSession<'a> {
+    ///Creates a session for a recording that has just been started
+    pub(crate) fn new(gopro: &'a GoPro) -> Self {
+        RecordingSession {
+            gopro,
+            stopped: false,
+        }
+    }
+
+    ///Returns how long the camera has been recording, as reported by the camera
+    pub async fn elapsed(&self) -> Result<Duration, Box<dyn Error>> {
+        let seconds = self
+            .gopro
+            .query_status_integer(StatusID::VideoProgressCounter)
+            .await?;
+        Ok(Duration::from_secs(seconds))
+    }
+
+    ///Adds a HiLight at the current point of the recording
+    pub async fn add_hilight(&self) -> Result<(), Box<dyn Error>> {
+        self.gopro
+            .send_command(GoProCommand::AddHilightDuringEncoding.as_ref())
+            .await
+    }
+
+    ///Stops the recording and waits for the camera to finish encoding it
+    pub async fn stop(mut self) -> Result<(), Box<dyn Error>> {
+        self.gopro
+            .send_command(GoProCommand::ShutterStop.as_ref())
+            .await?;
+        self.stopped = true;
+        self.gopro.wait_until_ready(STOP_TIMEOUT).await
+    }
+}
+
+impl Drop for RecordingSession<'_> {
+    fn drop(&mut self) {
+        if self.stopped {
+            return;
+        }
+        //Can't await in drop so the shutter is stopped from a task,
+        //which needs a runtime to exist
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let device = self.gopro.device.clone();
+        let exchange_lock = self.gopro.exchange_lock.clone();
+        let command = self.gopro.characteristics.command.clone();
+        runtime.spawn(async move {
+            let _ = crate::exchange(
+                &device,
+                &exchange_lock,
+                &command,
+                GoProCommand::ShutterStop.as_bytes(),
+                GPCharac::CommandResponse.to_uuid(),
+            )
+            .await;
+        });
+    }
+}
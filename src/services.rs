@@ -1,19 +1,28 @@
+use btleplug::api::Characteristic;
+use std::collections::BTreeSet;
+use std::error::Error;
 use uuid::Uuid;
 
 ///Macro for creating a Full GoPro UUID from a 16 bit UUID
-///NOTE: This macro will cause a runtime error if the UUID is not passed in as a u32
+///NOTE: The UUID is built at compile time, so the 16 bit UUID must be a constant u16.
+///Anything wider fails to compile rather than being truncated
 macro_rules! gp_uuid {
     ($x:expr) => {{
-        // Concatenate to form the full UUID
-        let full_uuid = format!("b5f9{}-aa8d-11e3-9046-0002a5d5c51b", $x);
-        Uuid::parse_str(&full_uuid).expect("Invalid UUID")
+        const SHORT: u16 = $x;
+        // Place the 16 bit UUID into the GoPro base UUID
+        const UUID: Uuid =
+            Uuid::from_u128(0xb5f9_0000_aa8d_11e3_9046_0002a5d5c51b | ((SHORT as u128) << 96));
+        UUID
     }};
 }
 
 #[test]
 fn test_macro() {
-    let uuid = gp_uuid!("0072");
-    assert_eq!(uuid, gp_uuid!("0072"));
+    let uuid = gp_uuid!(0x0072);
+    assert_eq!(
+        uuid,
+        Uuid::parse_str("b5f90072-aa8d-11e3-9046-0002a5d5c51b").unwrap()
+    );
 }
 
 ///Behavior for converting a datatype to a GoPro global UUID
@@ -40,16 +49,14 @@ impl ToUUID for GoProServices {
     fn to_uuid(&self) -> Uuid {
         match self {
             #[cfg(feature = "wifi")]
-            GoProServices::GoProWiFiAp => gp_uuid!("0001"),
+            GoProServices::GoProWiFiAp => gp_uuid!(0x0001),
             #[cfg(feature = "wifi")]
-            GoProServices::GoProCamManagement => gp_uuid!("0090"),
-            GoProServices::ControlAndQuery => gp_uuid!("FEA6"),
+            GoProServices::GoProCamManagement => gp_uuid!(0x0090),
+            GoProServices::ControlAndQuery => gp_uuid!(0xFEA6),
         }
     }
 }
 
-///FOR FUTURE USE
-///
 ///Represents the different characteristics that the GoProWiFiAp service has
 #[cfg(feature = "wifi")]
 pub enum GoProWifiApCharacteristics {
@@ -66,16 +73,14 @@ use GoProWifiApCharacteristics as GPWAC; //alias for conciseness
 impl ToUUID for GoProWifiApCharacteristics {
     fn to_uuid(&self) -> Uuid {
         match self {
-            GPWAC::SSID => gp_uuid!("0002"),
-            GPWAC::Password => gp_uuid!("0003"),
-            GPWAC::Power => gp_uuid!("0004"),
-            GPWAC::State => gp_uuid!("0005"),
+            GPWAC::SSID => gp_uuid!(0x0002),
+            GPWAC::Password => gp_uuid!(0x0003),
+            GPWAC::Power => gp_uuid!(0x0004),
+            GPWAC::State => gp_uuid!(0x0005),
         }
     }
 }
 
-///FOR FUTURE USE
-///
 ///Represents the different characteristics that the GoProCamManagement service has
 #[cfg(feature = "wifi")]
 pub enum GoProManagementCharacteristics {
@@ -89,8 +94,8 @@ use GoProManagementCharacteristics as GPMC; //alias for conciseness
 impl ToUUID for GoProManagementCharacteristics {
     fn to_uuid(&self) -> Uuid {
         match self {
-            GPMC::NetworkManagementCommand => gp_uuid!("0091"),
-            GPMC::NetworkManagementResponse => gp_uuid!("0092"),
+            GPMC::NetworkManagementCommand => gp_uuid!(0x0091),
+            GPMC::NetworkManagementResponse => gp_uuid!(0x0092),
         }
     }
 }
@@ -109,12 +114,67 @@ use GoProControlAndQueryCharacteristics as GPCAQ; //alias for conciseness
 impl ToUUID for GoProControlAndQueryCharacteristics {
     fn to_uuid(&self) -> Uuid {
         match self {
-            GPCAQ::Command => gp_uuid!("0072"),
-            GPCAQ::CommandResponse => gp_uuid!("0073"),
-            GPCAQ::Settings => gp_uuid!("0074"),
-            GPCAQ::SettingsResponse => gp_uuid!("0075"),
-            GPCAQ::Query => gp_uuid!("0076"),
-            GPCAQ::QueryResponse => gp_uuid!("0077"),
+            GPCAQ::Command => gp_uuid!(0x0072),
+            GPCAQ::CommandResponse => gp_uuid!(0x0073),
+            GPCAQ::Settings => gp_uuid!(0x0074),
+            GPCAQ::SettingsResponse => gp_uuid!(0x0075),
+            GPCAQ::Query => gp_uuid!(0x0076),
+            GPCAQ::QueryResponse => gp_uuid!(0x0077),
         }
     }
 }
+
+///The characteristics that the GoPro handle writes to and reads from,
+///resolved once when connecting so that they aren't searched for on every call
+#[derive(Clone)]
+pub(crate) struct CharacteristicTable {
+    pub(crate) command: Characteristic,
+    pub(crate) settings: Characteristic,
+    #[cfg(feature = "query")]
+    pub(crate) query: Characteristic,
+    #[cfg(feature = "wifi")]
+    pub(crate) wifi_ap_ssid: Option<Characteristic>,
+    #[cfg(feature = "wifi")]
+    pub(crate) wifi_ap_password: Option<Characteristic>,
+    #[cfg(feature = "wifi")]
+    pub(crate) network_management: Option<Characteristic>,
+}
+
+impl CharacteristicTable {
+    ///Finds the characteristics in the set discovered on a GoPro
+    ///
+    ///The control and query characteristics (and the ones their responses arrive on)
+    ///are required, those only used by optional features are not
+    ///
+    /// # Arguments
+    /// * `discovered` - The characteristics discovered on the GoPro
+    pub(crate) fn resolve(discovered: &BTreeSet<Characteristic>) -> Result<Self, Box<dyn Error>> {
+        let find = |uuid: Uuid| discovered.iter().find(|c| c.uuid == uuid).cloned();
+        let require = |name: &str, uuid: Uuid| {
+            find(uuid).ok_or_else(|| {
+                format!("Required characteristic {name} ({uuid}) not found on this GoPro")
+            })
+        };
+
+        for (name, uuid) in [
+            ("CommandResponse", GPCAQ::CommandResponse.to_uuid()),
+            ("SettingsResponse", GPCAQ::SettingsResponse.to_uuid()),
+            ("QueryResponse", GPCAQ::QueryResponse.to_uuid()),
+        ] {
+            require(name, uuid)?;
+        }
+
+        Ok(CharacteristicTable {
+            command: require("Command", GPCAQ::Command.to_uuid())?,
+            settings: require("Settings", GPCAQ::Settings.to_uuid())?,
+            #[cfg(feature = "query")]
+            query: require("Query", GPCAQ::Query.to_uuid())?,
+            #[cfg(feature = "wifi")]
+            wifi_ap_ssid: find(GPWAC::SSID.to_uuid()),
+            #[cfg(feature = "wifi")]
+            wifi_ap_password: find(GPWAC::Password.to_uuid()),
+            #[cfg(feature = "wifi")]
+            network_management: find(GPMC::NetworkManagementCommand.to_uuid()),
+        })
+    }
+}
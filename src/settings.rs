@@ -70,6 +70,8 @@ macro_rules! sendable_impl {
                 $response
             }
         }
+
+        from_ids_impl!($enum, $($variant => $bytes),*);
     };
 }
 
@@ -96,6 +98,27 @@ macro_rules! sendable_impl_complex {
                 }
             }
         }
+
+        from_ids_impl!($enum, $($variant => $bytes),*);
+    };
+}
+
+/// Implements the reverse of `as_bytes` for a given enum, looking a variant up
+/// by the setting and option IDs its bytes hold
+macro_rules! from_ids_impl {
+    ($enum:ty, $($variant:path => $bytes:expr),*) => {
+        impl $enum {
+            ///Returns the variant with the given setting and option IDs, if there is one
+            pub(crate) fn from_ids(setting_id: u8, option_id: u8) -> Option<Self> {
+                $(
+                    let bytes: &[u8] = $bytes;
+                    if bytes[1] == setting_id && bytes[3] == option_id {
+                        return Some($variant);
+                    }
+                )*
+                None
+            }
+        }
     };
 }
 
@@ -105,6 +128,7 @@ macro_rules! sendable_impl_complex {
 /// The byte arrays in this enum were taken directly from the GoPro Open Spec:
 ///
 ///<https://gopro.github.io/OpenGoPro/ble_2_0#settings-quick-reference>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GoProSetting {
     Resolution(Hero11Resolution),
     Fps(Hero11FPS),
@@ -127,6 +151,43 @@ pub enum GoProSetting {
 }
 
 use GoProSetting as GPS;
+impl GPS {
+    ///Returns the ID of the setting, which the HTTP API takes as the `setting` parameter
+    pub fn setting_id(&self) -> u8 {
+        //The BLE message is [length, setting id, option length, option id]
+        self.as_bytes()[1]
+    }
+
+    ///Returns the ID of the option, which the HTTP API takes as the `option` parameter
+    pub fn option_id(&self) -> u8 {
+        self.as_bytes()[3]
+    }
+
+    ///Returns the setting with the given setting and option IDs, as reported by the
+    ///camera, or None if it isn't one this crate knows
+    pub fn from_ids(setting_id: u8, option_id: u8) -> Option<Self> {
+        let (s, o) = (setting_id, option_id);
+        None.or_else(|| Hero11Resolution::from_ids(s, o).map(GPS::Resolution))
+            .or_else(|| Hero11FPS::from_ids(s, o).map(GPS::Fps))
+            .or_else(|| Hero11AutoPowerDown::from_ids(s, o).map(GPS::AutoPowerDown))
+            .or_else(|| Hero11VideoDigitalLense::from_ids(s, o).map(GPS::VideoDigitalLense))
+            .or_else(|| Hero11PhotoDigitalLense::from_ids(s, o).map(GPS::PhotoDigitalLense))
+            .or_else(|| Hero11TimeLapseDigitalLense::from_ids(s, o).map(GPS::TimeLapseDigitalLense))
+            .or_else(|| Hero11MediaFormat::from_ids(s, o).map(GPS::MediaFormat))
+            .or_else(|| AntiFlicker::from_ids(s, o).map(GPS::AntiFlicker))
+            .or_else(|| Hero11Hypersmooth::from_ids(s, o).map(GPS::Hypersmooth))
+            .or_else(|| Hero11HorizonLeveling::from_ids(s, o).map(GPS::HorizonLeveling))
+            .or_else(|| Hero11MaxLense::from_ids(s, o).map(GPS::MaxLense))
+            .or_else(|| Hero11Hindsight::from_ids(s, o).map(GPS::Hindsight))
+            .or_else(|| Hero11Controls::from_ids(s, o).map(GPS::Controls))
+            .or_else(|| Hero11Speed::from_ids(s, o).map(GPS::Speed))
+            .or_else(|| Hero11NightPhoto::from_ids(s, o).map(GPS::NightPhoto))
+            .or_else(|| Hero11WirelessBand::from_ids(s, o).map(GPS::WirelessBand))
+            .or_else(|| Hero11TrailLength::from_ids(s, o).map(GPS::TrailLength))
+            .or_else(|| Hero11VideoMode::from_ids(s, o).map(GPS::VideoMode))
+    }
+}
+
 impl Sendable for GPS {
     fn as_bytes(&self) -> &'static [u8] {
         match self {
@@ -181,6 +242,7 @@ impl AsRef<GoProSetting> for GoProSetting {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(non_camel_case_types)]
 pub enum Hero11Resolution {
     Res4K,
@@ -208,6 +270,7 @@ sendable_impl!(
     Hero11Resolution::Res5_3K => &[0x03, 0x02, 0x01, 0x64]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11FPS {
     Fps240,
     Fps120,
@@ -234,6 +297,7 @@ sendable_impl!(
     Hero11FPS::Fps200 => &[0x03, 0x03, 0x01, 0x0D]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11AutoPowerDown {
     Never,
     OneMinute,
@@ -252,6 +316,7 @@ sendable_impl!(
     Hero11AutoPowerDown::ThirtyMinutes => &[0x03, 0x3B, 0x01, 0x07]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11VideoDigitalLense {
     Wide,
     Superview,
@@ -274,6 +339,7 @@ sendable_impl!(
     Hero11VideoDigitalLense::LinearHorizonLock => &[0x03, 0x79, 0x01, 0x0A]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11PhotoDigitalLense {
     MaxSuperview,
     Wide,
@@ -288,6 +354,7 @@ sendable_impl!(
     Hero11PhotoDigitalLense::Linear => &[0x03, 0x7A, 0x01, 0x66]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11TimeLapseDigitalLense {
     MaxSuperview,
     Wide,
@@ -302,6 +369,7 @@ sendable_impl!(
     Hero11TimeLapseDigitalLense::Linear => &[0x03, 0x7B, 0x01, 0x66]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11MediaFormat {
     TimeLapseVideo,
     TimeLapsePhoto,
@@ -318,6 +386,7 @@ sendable_impl!(
     Hero11MediaFormat::NightLapseVideo => &[0x03, 0x80, 0x01, 0x1A]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AntiFlicker {
     SixtyHertz,
     FiftyHertz,
@@ -330,6 +399,7 @@ sendable_impl!(
     AntiFlicker::FiftyHertz => &[0x03, 0x86, 0x01, 0x03]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11Hypersmooth {
     Off,
     Low,
@@ -346,6 +416,7 @@ sendable_impl!(
     Hero11Hypersmooth::Auto => &[0x03, 0x87, 0x01, 0x04]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11HorizonLeveling {
     VideoOff,
     VideoLocked,
@@ -365,6 +436,7 @@ sendable_impl_complex!(
     &[0x02, 0x97, 0x00] // Response bytes
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11MaxLense {
     Off,
     On,
@@ -377,6 +449,7 @@ sendable_impl!(
     Hero11MaxLense::On => &[0x03, 0xA2, 0x01, 0x01]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11Hindsight {
     FifteenSeconds,
     ThirtySeconds,
@@ -391,6 +464,7 @@ sendable_impl!(
     Hero11Hindsight::Off => &[0x03, 0xA7, 0x01, 0x04]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11Controls {
     Easy,
     Pro,
@@ -403,6 +477,7 @@ sendable_impl!(
     Hero11Controls::Pro => &[0x03, 0xAF, 0x01, 0x01]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11Speed {
     UltraSlowMo8X,
     SuperSlowMo4X,
@@ -467,6 +542,7 @@ sendable_impl!(
     Hero11Speed::SuperSlowMo4x2_7K50Hz => &[0x03, 0xB0, 0x01, 0x1B]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11NightPhoto {
     Off,
     On,
@@ -479,6 +555,7 @@ sendable_impl!(
     Hero11NightPhoto::On => &[0x03, 0xB1, 0x01, 0x01]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11WirelessBand {
     TwoPointFourGhz,
     FiveGhz,
@@ -491,6 +568,7 @@ sendable_impl!(
     Hero11WirelessBand::FiveGhz => &[0x03, 0xB2, 0x01, 0x01]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11TrailLength {
     Short,
     Long,
@@ -505,6 +583,7 @@ sendable_impl!(
     Hero11TrailLength::Max => &[0x03, 0xB3, 0x01, 0x03]
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Hero11VideoMode {
     HighestQuality,
     ExtendedBattery,
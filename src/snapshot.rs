@@ -0,0 +1,59 @@
+use crate::query::{QueryResponseIntepretation as QRI, StatusID, StatusKind};
+#[cfg(feature = "settings")]
+use crate::settings::GoProSetting;
+use std::collections::{BTreeMap, HashMap};
+
+///The statuses and settings of a camera at one moment
+///
+///Snapshots are the same whichever transport fetched them, see [`crate::Controller::snapshot`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CameraSnapshot {
+    ///The value of every status the camera reported that this crate knows
+    ///
+    ///Integer statuses (those interpreted as a ByteVec) always hold 4 big endian
+    ///bytes here, as BLE doesn't send every one of them at the same length
+    pub statuses: HashMap<StatusID, QRI>,
+    ///The option ID of every setting the camera reported, keyed by setting ID
+    pub setting_options: BTreeMap<u8, u32>,
+}
+
+impl CameraSnapshot {
+    ///Builds a snapshot from statuses and settings fetched over either transport
+    pub(crate) fn new(statuses: HashMap<StatusID, QRI>, setting_options: HashMap<u8, u32>) -> Self {
+        let statuses = statuses
+            .into_iter()
+            .map(|(id, value)| {
+                let value = match (id.kind(), value.as_integer()) {
+                    (StatusKind::ByteVec, Some(n)) => id.interpret_integer(n).unwrap_or(value),
+                    _ => value,
+                };
+                (id, value)
+            })
+            .collect();
+        CameraSnapshot {
+            statuses,
+            setting_options: setting_options.into_iter().collect(),
+        }
+    }
+
+    ///Returns the value of a status, if the camera reported it
+    pub fn status(&self, id: StatusID) -> Option<&QRI> {
+        self.statuses.get(&id)
+    }
+
+    #[cfg(feature = "settings")]
+    ///Returns the settings the camera reported that this crate knows, ordered by setting ID
+    pub fn settings(&self) -> Vec<GoProSetting> {
+        self.setting_options
+            .iter()
+            .filter_map(|(id, option)| GoProSetting::from_ids(*id, (*option).try_into().ok()?))
+            .collect()
+    }
+
+    #[cfg(feature = "settings")]
+    ///Returns the setting with the given ID, if the camera reported it and this crate knows it
+    pub fn setting(&self, setting_id: u8) -> Option<GoProSetting> {
+        let option = (*self.setting_options.get(&setting_id)?).try_into().ok()?;
+        GoProSetting::from_ids(setting_id, option)
+    }
+}
@@ -0,0 +1,304 @@
+use crate::packet::{self, Accumulator};
+use crate::query::{
+    GoProQuery, QueryResponse, QueryResponseIntepretation as QRI, StatusID, ASYNC_STATUS_UPDATE_ID,
+};
+use crate::services::{GoProControlAndQueryCharacteristics as GPCharac, ToUUID};
+use btleplug::api::{Characteristic, Peripheral as _};
+use btleplug::platform::Peripheral;
+use futures::stream::StreamExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+
+///The statuses that the GoPro handle registers for and keeps up to date
+const TRACKED_STATUSES: [StatusID; 6] = [
+    StatusID::SystemReady,
+    StatusID::SystemBusy,
+    StatusID::EncodingActive,
+    StatusID::SystemHot,
+    StatusID::VideoLowTempAlert,
+    StatusID::CurrentMode,
+];
+
+///Flatmode IDs (reported by [`StatusID::CurrentMode`]) in which the camera captures video
+///
+///NOTE: The IDs were taken directly from the GoPro Open Spec:
+///<https://gopro.github.io/OpenGoPro/ble_2_0#status-ids>
+const VIDEO_FLATMODES: [u32; 6] = [12, 13, 15, 24, 26, 27];
+
+///The overall state of the camera, derived from the statuses it reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraState {
+    ///Ready for commands and not doing anything
+    Idle,
+    ///Capturing video
+    Recording,
+    ///Capturing anything other than video (photos, bursts, timelapse photos)
+    Encoding,
+    ///Busy (e.g. writing media after a capture) and not accepting commands
+    Busy,
+    ///Reporting a temperature alert, either too hot or too cold to keep recording
+    Overheated,
+    ///Connected but not ready, as when asleep or still waking up
+    Asleep,
+    ///Not connected, or no statuses have been received yet
+    Disconnected,
+}
+
+///The most recent values the camera has reported for the statuses
+///that the GoPro handle keeps track of
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CameraStatus {
+    values: HashMap<StatusID, QRI>,
+}
+
+impl CameraStatus {
+    ///Returns the last reported value of a status, if it is tracked and has been reported
+    pub fn get(&self, id: StatusID) -> Option<&QRI> {
+        self.values.get(&id)
+    }
+
+    ///Returns the last reported value of a boolean status
+    pub fn flag(&self, id: StatusID) -> Option<bool> {
+        match self.get(id) {
+            Some(QRI::Bool(b)) => Some(*b),
+            _ => None,
+        }
+    }
+
+    ///Returns true if the camera has reported that it is ready, not busy and not encoding
+    pub fn is_ready(&self) -> bool {
+        self.flag(StatusID::SystemReady) == Some(true)
+            && self.flag(StatusID::SystemBusy) == Some(false)
+            && self.flag(StatusID::EncodingActive) == Some(false)
+    }
+
+    ///Returns the flatmode the camera is in, if it has been reported
+    pub fn current_mode(&self) -> Option<u32> {
+        self.get(StatusID::CurrentMode)?
+            .as_integer()?
+            .try_into()
+            .ok()
+    }
+
+    ///Derives the overall state of the camera from the reported statuses
+    pub fn state(&self) -> CameraState {
+        if self.values.is_empty() {
+            return CameraState::Disconnected;
+        }
+        if self.flag(StatusID::SystemHot) == Some(true)
+            || self.flag(StatusID::VideoLowTempAlert) == Some(true)
+        {
+            return CameraState::Overheated;
+        }
+        if self.flag(StatusID::EncodingActive) == Some(true) {
+            return match self.current_mode() {
+                Some(mode) if VIDEO_FLATMODES.contains(&mode) => CameraState::Recording,
+                _ => CameraState::Encoding,
+            };
+        }
+        if self.flag(StatusID::SystemBusy) == Some(true) {
+            return CameraState::Busy;
+        }
+        if self.flag(StatusID::SystemReady) != Some(true) {
+            return CameraState::Asleep;
+        }
+        CameraState::Idle
+    }
+
+    ///Records the values held by a set of query responses
+    fn update(&mut self, responses: Vec<QueryResponse>) {
+        for response in responses {
+            let (Ok(id), Some(value)) =
+                (StatusID::try_from(response.status_id), response.interpret())
+            else {
+                continue;
+            };
+            self.values.insert(id, value);
+        }
+    }
+}
+
+///Handle to the background task that keeps a [`CameraStatus`] and the
+///[`CameraState`] derived from it up to date from the status updates the camera pushes
+pub(crate) struct StatusMonitor {
+    task: JoinHandle<()>,
+    status: watch::Receiver<CameraStatus>,
+    state: Arc<watch::Sender<CameraState>>,
+}
+
+impl StatusMonitor {
+    ///Spawns the task, which registers for status updates and then listens for them
+    ///
+    ///If registering fails or the notifications stop the task ends, the status
+    ///receivers will see the channel close and the state becomes Disconnected
+    ///
+    /// # Arguments
+    /// * `device` - The GoPro peripheral to monitor
+    /// * `exchange_lock` - The lock shared with the GoPro handle
+    /// * `query` - The query characteristic to register with
+    pub(crate) fn spawn(
+        device: Peripheral,
+        exchange_lock: Arc<Mutex<()>>,
+        query: Characteristic,
+    ) -> Self {
+        let (sender, status) = watch::channel(CameraStatus::default());
+        let (state, _) = watch::channel(CameraState::Disconnected);
+        let state = Arc::new(state);
+        let task_state = state.clone();
+
+        let task = tokio::spawn(async move {
+            //Keep the derived state in step with every change to the status
+            let update = |responses: Vec<QueryResponse>| {
+                sender.send_modify(|status| status.update(responses));
+                let new_state = sender.borrow().state();
+                task_state.send_if_modified(|state| {
+                    let changed = *state != new_state;
+                    *state = new_state;
+                    changed
+                });
+            };
+
+            monitor(&device, &exchange_lock, &query, update).await;
+            task_state.send_replace(CameraState::Disconnected);
+        });
+
+        StatusMonitor {
+            task,
+            status,
+            state,
+        }
+    }
+
+    ///Returns a new receiver for the camera status
+    pub(crate) fn subscribe(&self) -> watch::Receiver<CameraStatus> {
+        self.status.clone()
+    }
+
+    ///Returns a new receiver for the camera state
+    pub(crate) fn subscribe_state(&self) -> watch::Receiver<CameraState> {
+        self.state.subscribe()
+    }
+
+    ///Stops the task, the status will no longer be updated and the state becomes Disconnected
+    pub(crate) fn stop(&self) {
+        self.task.abort();
+        self.state.send_replace(CameraState::Disconnected);
+    }
+}
+
+impl Drop for StatusMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+///Registers for updates to the tracked statuses and passes each set of values the
+///camera reports to `update`, returning when registering fails or the notifications end
+async fn monitor(
+    device: &Peripheral,
+    exchange_lock: &Mutex<()>,
+    query: &Characteristic,
+    update: impl Fn(Vec<QueryResponse>),
+) {
+    let Ok(mut notifications) = device.notifications().await else {
+        return;
+    };
+
+    let register = GoProQuery::RegisterStatusValueUpdates(TRACKED_STATUSES.to_vec());
+    let Ok(res) = crate::exchange(
+        device,
+        exchange_lock,
+        query,
+        &register.as_bytes(),
+        GPCharac::QueryResponse.to_uuid(),
+    )
+    .await
+    else {
+        return;
+    };
+    let Ok(initial) = QueryResponse::deserialize_many(packet::payload(&res.value)) else {
+        return;
+    };
+    update(initial);
+
+    let mut accumulator = Accumulator::default();
+    while let Some(notification) = notifications.next().await {
+        if notification.uuid != GPCharac::QueryResponse.to_uuid() {
+            continue;
+        }
+        let Some(message) = accumulator.push(&notification.value) else {
+            continue;
+        };
+        let payload = packet::payload(&message);
+        if payload.first() != Some(&ASYNC_STATUS_UPDATE_ID) {
+            continue;
+        }
+        if let Ok(values) = QueryResponse::deserialize_many(payload) {
+            update(values);
+        }
+    }
+}
+
+#[test]
+fn test_camera_status_ready() {
+    let mut status = CameraStatus::default();
+    assert!(!status.is_ready());
+
+    // Ready, busy and encoding as reported on registration
+    status.update(
+        QueryResponse::deserialize_many(&[
+            0x53, 0x00, 0x52, 0x01, 0x01, 0x08, 0x01, 0x01, 0x0A, 0x01, 0x00,
+        ])
+        .unwrap(),
+    );
+    assert!(!status.is_ready());
+
+    // Pushed update that the camera is no longer busy
+    status.update(QueryResponse::deserialize_many(&[0x93, 0x00, 0x08, 0x01, 0x00]).unwrap());
+    assert!(status.is_ready());
+
+    // A malformed update is skipped rather than taking down the monitor
+    status.update(QueryResponse::deserialize_many(&[0x93, 0x00, 0x08, 0x00]).unwrap());
+    assert!(status.is_ready());
+}
+
+#[test]
+fn test_camera_state() {
+    let mut status = CameraStatus::default();
+    assert_eq!(status.state(), CameraState::Disconnected);
+
+    // Ready, not busy, not encoding, not hot, not cold and in Video (12)
+    status.update(
+        QueryResponse::deserialize_many(&[
+            0x53, 0x00, 0x52, 0x01, 0x01, 0x08, 0x01, 0x00, 0x0A, 0x01, 0x00, 0x06, 0x01, 0x00,
+            0x55, 0x01, 0x00, 0x59, 0x01, 0x0C,
+        ])
+        .unwrap(),
+    );
+    assert_eq!(status.state(), CameraState::Idle);
+
+    status.update(QueryResponse::deserialize_many(&[0x93, 0x00, 0x0A, 0x01, 0x01]).unwrap());
+    assert_eq!(status.state(), CameraState::Recording);
+
+    // Switched to Photo (17) while encoding
+    status.update(QueryResponse::deserialize_many(&[0x93, 0x00, 0x59, 0x01, 0x11]).unwrap());
+    assert_eq!(status.state(), CameraState::Encoding);
+
+    status.update(
+        QueryResponse::deserialize_many(&[0x93, 0x00, 0x0A, 0x01, 0x00, 0x08, 0x01, 0x01]).unwrap(),
+    );
+    assert_eq!(status.state(), CameraState::Busy);
+
+    status.update(QueryResponse::deserialize_many(&[0x93, 0x00, 0x06, 0x01, 0x01]).unwrap());
+    assert_eq!(status.state(), CameraState::Overheated);
+
+    status.update(
+        QueryResponse::deserialize_many(&[
+            0x93, 0x00, 0x06, 0x01, 0x00, 0x08, 0x01, 0x00, 0x52, 0x01, 0x00,
+        ])
+        .unwrap(),
+    );
+    assert_eq!(status.state(), CameraState::Asleep);
+}
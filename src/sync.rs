@@ -0,0 +1,352 @@
+//Incremental syncing of the media on a camera to a local directory
+
+use crate::http::GoProHttp;
+use crate::media::MediaFile;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+///The name of the manifest kept in the root of a synced directory
+pub const SYNC_MANIFEST_FILE_NAME: &str = ".gopro-sync.json";
+
+///How [`GoProHttp::sync`] behaves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncOptions {
+    ///Work out what would be downloaded and deleted without doing either
+    pub dry_run: bool,
+    ///Delete media from the camera once every file of it has been downloaded and verified,
+    ///which fails the sync if the camera is encoding
+    pub delete_after_download: bool,
+    ///The most files to download at once
+    pub concurrency: usize,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        SyncOptions {
+            dry_run: false,
+            delete_after_download: false,
+            concurrency: 2,
+        }
+    }
+}
+
+///What [`GoProHttp::sync`] did, or would have done in a dry run
+///
+///Files are identified by their path on the camera, e.g. `100GOPRO/GX010002.MP4`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub downloaded: Vec<String>,
+    ///Files that were skipped because the manifest shows they were already downloaded
+    pub already_present: Vec<String>,
+    ///Media deleted from the camera
+    pub deleted: Vec<String>,
+    ///Files that couldn't be downloaded or deleted, and why
+    pub failed: Vec<(String, String)>,
+}
+
+///A file recorded in the manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ManifestEntry {
+    size: u64,
+    created: u64,
+}
+
+///The files that have been downloaded from each camera, keyed by serial number and
+///then by path on the camera
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct Manifest(BTreeMap<String, BTreeMap<String, ManifestEntry>>);
+
+impl Manifest {
+    async fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        tokio::fs::write(path, serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+
+    ///Whether a file was downloaded and is still on disk at the size it was downloaded at
+    fn contains(&self, serial: &str, file: &MediaFile, local: &Path) -> bool {
+        let Some(entry) = self.0.get(serial).and_then(|files| files.get(&file.path())) else {
+            return false;
+        };
+        std::fs::metadata(local).is_ok_and(|m| m.len() == entry.size)
+    }
+
+    fn insert(&mut self, serial: &str, file: &MediaFile, size: u64) {
+        self.0.entry(serial.to_string()).or_default().insert(
+            file.path(),
+            ManifestEntry {
+                size,
+                created: file.created,
+            },
+        );
+    }
+}
+
+///Returns a file for each member of grouped media, or the file itself otherwise
+fn members(file: &MediaFile) -> Vec<MediaFile> {
+    file.member_names()
+        .into_iter()
+        .map(|name| MediaFile {
+            name,
+            ..file.clone()
+        })
+        .collect()
+}
+
+///Downloads the media on the camera that hasn't been downloaded to `root` yet
+///
+///See [`GoProHttp::sync`]
+pub(crate) async fn sync(
+    http: &GoProHttp,
+    root: &Path,
+    options: &SyncOptions,
+) -> Result<SyncReport, Box<dyn Error>> {
+    let serial = http.camera_info().await?.serial_number;
+    if serial.is_empty() {
+        return Err("The camera didn't report its serial number".into());
+    }
+    let camera_root = root.join(&serial);
+    let manifest_path = root.join(SYNC_MANIFEST_FILE_NAME);
+    let manifest = Manifest::load(&manifest_path).await?;
+    let files: Vec<MediaFile> = http
+        .media_list()
+        .await?
+        .into_iter()
+        .flat_map(|directory| directory.files)
+        .collect();
+
+    let mut report = SyncReport::default();
+    //The index of the media each file belongs to, the file and where it is saved
+    let mut downloads: Vec<(usize, MediaFile, PathBuf)> = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        for member in members(file) {
+            let local = camera_root.join(&member.directory).join(&member.name);
+            match manifest.contains(&serial, &member, &local) {
+                true => report.already_present.push(member.path()),
+                false => downloads.push((index, member, local)),
+            }
+        }
+    }
+
+    if options.dry_run {
+        report.downloaded = downloads.iter().map(|(_, f, _)| f.path()).collect();
+        if options.delete_after_download {
+            report.deleted = files.iter().map(MediaFile::path).collect();
+        }
+        return Ok(report);
+    }
+
+    let manifest = Mutex::new(manifest);
+    let results: Vec<_> = stream::iter(downloads)
+        .map(|(index, member, local)| {
+            let (serial, manifest, manifest_path) = (&serial, &manifest, &manifest_path);
+            async move {
+                let result = async {
+                    if let Some(parent) = local.parent() {
+                        tokio::fs::create_dir_all(parent).await?;
+                    }
+                    //The media list only has the size of a whole group, so each member is
+                    //checked against the size in its own media info instead
+                    let download = match member.group {
+                        Some(_) => MediaFile {
+                            size: http.media_info(&member).await?.size,
+                            group: None,
+                            ..member.clone()
+                        },
+                        None => member.clone(),
+                    };
+                    let size = http.download(&download, &local, |_| {}).await?;
+                    //Saved after every file so an interrupted sync doesn't download it again,
+                    //with the lock held so saves land in order
+                    let mut manifest = manifest.lock().await;
+                    manifest.insert(serial, &member, size);
+                    manifest.save(manifest_path).await
+                }
+                .await;
+                (index, member, result.map_err(|e| e.to_string()))
+            }
+        })
+        .buffer_unordered(options.concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut incomplete = HashSet::new();
+    for (index, member, result) in results {
+        match result {
+            Ok(()) => report.downloaded.push(member.path()),
+            Err(e) => {
+                incomplete.insert(index);
+                report.failed.push((member.path(), e));
+            }
+        }
+    }
+    report.downloaded.sort();
+    report.failed.sort();
+
+    if options.delete_after_download {
+        http.ensure_not_encoding().await?;
+        for (index, file) in files.iter().enumerate() {
+            if incomplete.contains(&index) {
+                continue;
+            }
+            match http.delete_media(file).await {
+                Ok(()) => report.deleted.push(file.path()),
+                Err(e) => report.failed.push((file.path(), e.to_string())),
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+use crate::mock_http::{self, Response};
+
+#[tokio::test]
+async fn test_sync() {
+    //Two photos and a burst of two, where the second photo can't be downloaded
+    let list = r#"{"id": "1", "media": [{"d": "100GOPRO", "fs": [
+        {"n": "GOPR0001.JPG", "cre": "1", "mod": "1", "s": "3"},
+        {"n": "GOPR0002.JPG", "cre": "2", "mod": "2", "s": "3"},
+        {"n": "G0010003.JPG", "g": "1", "b": "3", "l": "4", "t": "b", "cre": "3", "mod": "3", "s": "4"}
+    ]}]}"#;
+    let (base_url, log) = mock_http::serve(move |request| match request.path.as_str() {
+        "/gopro/camera/info" => Response::ok(r#"{"serial_number": "C3461324698034"}"#),
+        "/gopro/media/list" => Response::ok(list),
+        "/videos/DCIM/100GOPRO/GOPR0001.JPG" => Response::ok("abc"),
+        "/videos/DCIM/100GOPRO/G0010003.JPG" | "/videos/DCIM/100GOPRO/G0010004.JPG" => {
+            Response::ok("de")
+        }
+        path if path.starts_with("/gopro/media/info?path=100GOPRO%2FG001000") => {
+            Response::ok(r#"{"s": "2"}"#)
+        }
+        "/gopro/camera/state" => Response::ok(r#"{"status": {"10": 0}}"#),
+        path if path.starts_with("/gopro/media/delete/") => Response::ok("{}"),
+        _ => Response::status(404),
+    })
+    .await;
+    let http = GoProHttp::with_base_url(base_url).unwrap();
+    let root = std::env::temp_dir().join(format!("gopro-sync-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let downloads = |log: &mock_http::Log| {
+        log.lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.path.starts_with("/videos/"))
+            .count()
+    };
+
+    let dry_run = SyncOptions {
+        dry_run: true,
+        delete_after_download: true,
+        ..Default::default()
+    };
+    let report = http.sync(&root, &dry_run).await.unwrap();
+    assert_eq!(report.downloaded.len(), 4);
+    assert_eq!(report.deleted.len(), 3);
+    assert!(!root.exists());
+    assert_eq!(downloads(&log), 0);
+
+    let report = http.sync(&root, &SyncOptions::default()).await.unwrap();
+    assert_eq!(
+        report.downloaded,
+        vec![
+            "100GOPRO/G0010003.JPG",
+            "100GOPRO/G0010004.JPG",
+            "100GOPRO/GOPR0001.JPG"
+        ]
+    );
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, "100GOPRO/GOPR0002.JPG");
+    let photo = root.join("C3461324698034/100GOPRO/GOPR0001.JPG");
+    assert_eq!(std::fs::read(photo).unwrap(), b"abc");
+    assert!(root.join(SYNC_MANIFEST_FILE_NAME).exists());
+    assert_eq!(downloads(&log), 4);
+
+    //Only the file that failed is downloaded again, and only complete media is deleted
+    let options = SyncOptions {
+        delete_after_download: true,
+        concurrency: 1,
+        ..Default::default()
+    };
+    let report = http.sync(&root, &options).await.unwrap();
+    assert!(report.downloaded.is_empty());
+    assert_eq!(report.already_present.len(), 3);
+    assert_eq!(
+        report.deleted,
+        vec!["100GOPRO/GOPR0001.JPG", "100GOPRO/G0010003.JPG"]
+    );
+    assert_eq!(downloads(&log), 5);
+    let deletes: Vec<String> = log
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|r| r.path.starts_with("/gopro/media/delete/"))
+        .map(|r| r.path.clone())
+        .collect();
+    assert_eq!(
+        deletes,
+        vec![
+            "/gopro/media/delete/file?path=100GOPRO%2FGOPR0001.JPG",
+            "/gopro/media/delete/group?path=100GOPRO%2FG0010003.JPG"
+        ]
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_sync_truncated_group_member() {
+    //A burst of two where the camera sends only part of the second photo
+    let list = r#"{"id": "1", "media": [{"d": "100GOPRO", "fs": [
+        {"n": "GOPR0001.JPG", "cre": "1", "mod": "1", "s": "3"},
+        {"n": "G0010003.JPG", "g": "1", "b": "3", "l": "4", "t": "b", "cre": "3", "mod": "3", "s": "6"}
+    ]}]}"#;
+    let (base_url, log) = mock_http::serve(move |request| match request.path.as_str() {
+        "/gopro/camera/info" => Response::ok(r#"{"serial_number": "C3461324698035"}"#),
+        "/gopro/media/list" => Response::ok(list),
+        "/gopro/media/info?path=100GOPRO%2FG0010003.JPG" => Response::ok(r#"{"s": "2"}"#),
+        "/gopro/media/info?path=100GOPRO%2FG0010004.JPG" => Response::ok(r#"{"s": "4"}"#),
+        "/videos/DCIM/100GOPRO/GOPR0001.JPG" => Response::ok("abc"),
+        "/videos/DCIM/100GOPRO/G0010003.JPG" | "/videos/DCIM/100GOPRO/G0010004.JPG" => {
+            Response::ok("de")
+        }
+        "/gopro/camera/state" => Response::ok(r#"{"status": {"10": 0}}"#),
+        path if path.starts_with("/gopro/media/delete/") => Response::ok("{}"),
+        _ => Response::status(404),
+    })
+    .await;
+    let http = GoProHttp::with_base_url(base_url).unwrap();
+    let root = std::env::temp_dir().join(format!("gopro-sync-truncated-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+
+    let options = SyncOptions {
+        delete_after_download: true,
+        ..Default::default()
+    };
+    let report = http.sync(&root, &options).await.unwrap();
+    assert_eq!(
+        report.downloaded,
+        vec!["100GOPRO/G0010003.JPG", "100GOPRO/GOPR0001.JPG"]
+    );
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, "100GOPRO/G0010004.JPG");
+    assert_eq!(report.deleted, vec!["100GOPRO/GOPR0001.JPG"]);
+    assert!(!root.join("C3461324698035/100GOPRO/G0010004.JPG").exists());
+    assert!(log
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|r| !r.path.starts_with("/gopro/media/delete/group")));
+    std::fs::remove_dir_all(&root).unwrap();
+}
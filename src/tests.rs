@@ -267,3 +267,318 @@ async fn reset_testing() {
 
     gopro.disconnect_and_poweroff().await.unwrap();
 }
+
+#[tokio::test]
+async fn test_keep_alive() {
+    let mut central = init(None).await.unwrap();
+    let mut devices = scan(&mut central).await.unwrap();
+    devices.retain(|d| d.contains("GoPro"));
+    assert!(!devices.is_empty(), "No GoPro devices found");
+
+    //Other options only exist with some features enabled
+    #[allow(clippy::needless_update)]
+    let options = ConnectOptions {
+        keep_alive: Some(DEFAULT_KEEP_ALIVE_INTERVAL),
+        ..Default::default()
+    };
+    let gopro = connect_with_options(devices.first().unwrap().clone(), &mut central, options)
+        .await
+        .unwrap();
+
+    println!("Connected to GoPro");
+
+    let mut events = gopro.keep_alive_events().unwrap();
+    for _ in 0..3 {
+        let event = events.recv().await.unwrap();
+        println!("Keep alive event: {:?}", event);
+        assert_eq!(event, KeepAliveEvent::Sent);
+    }
+
+    println!("Disconnecting from GoPro");
+    gopro.disconnect().await.unwrap();
+}
+
+#[cfg(all(feature = "query", feature = "settings"))]
+#[tokio::test]
+async fn test_wait_for_ready() {
+    let mut central = init(None).await.unwrap();
+    let mut devices = scan(&mut central).await.unwrap();
+    devices.retain(|d| d.contains("GoPro"));
+    assert!(!devices.is_empty(), "No GoPro devices found");
+
+    let options = ConnectOptions {
+        wait_for_ready: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+    let gopro = connect_with_options(devices.first().unwrap().clone(), &mut central, options)
+        .await
+        .unwrap();
+
+    println!("Connected to GoPro");
+
+    gopro
+        .wait_until_ready(Duration::from_secs(10))
+        .await
+        .unwrap();
+    println!("Starting Shutter");
+    gopro
+        .send_command(GoProCommand::ShutterStart.as_ref())
+        .await
+        .unwrap();
+
+    time::sleep(Duration::from_secs(3)).await;
+    println!("Stopping Shutter");
+    gopro
+        .send_command(GoProCommand::ShutterStop.as_ref())
+        .await
+        .unwrap();
+
+    // Without waiting for the camera to finish encoding this fails intermittently
+    println!("Setting Video Lense to Hyperview");
+    gopro
+        .send_setting(GoProSetting::VideoDigitalLense(Hero11VideoDigitalLense::Hyperview).as_ref())
+        .await
+        .unwrap();
+
+    println!("Powering off");
+    gopro.disconnect_and_poweroff().await.unwrap();
+}
+
+#[cfg(feature = "query")]
+#[tokio::test]
+async fn test_camera_state() {
+    let mut central = init(None).await.unwrap();
+    let mut devices = scan(&mut central).await.unwrap();
+    devices.retain(|d| d.contains("GoPro"));
+    assert!(!devices.is_empty(), "No GoPro devices found");
+
+    let gopro = connect(devices.first().unwrap().clone(), &mut central)
+        .await
+        .unwrap();
+
+    println!("Connected to GoPro");
+
+    let mut state = gopro.state();
+    time::timeout(
+        Duration::from_secs(10),
+        state.wait_for(|s| *s == CameraState::Idle),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    println!("Camera is idle");
+
+    println!("Starting Shutter");
+    gopro
+        .send_command(GoProCommand::ShutterStart.as_ref())
+        .await
+        .unwrap();
+
+    time::timeout(
+        Duration::from_secs(10),
+        state.wait_for(|s| *s == CameraState::Recording),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    println!("Camera is recording");
+
+    time::sleep(Duration::from_secs(3)).await;
+    println!("Stopping Shutter");
+    gopro
+        .send_command(GoProCommand::ShutterStop.as_ref())
+        .await
+        .unwrap();
+
+    while state.changed().await.is_ok() {
+        println!("State: {:?}", *state.borrow());
+        if *state.borrow() == CameraState::Idle {
+            break;
+        }
+    }
+
+    println!("Powering off");
+    gopro.disconnect_and_poweroff().await.unwrap();
+}
+
+#[cfg(feature = "query")]
+#[tokio::test]
+async fn test_recording_session() {
+    let mut central = init(None).await.unwrap();
+    let mut devices = scan(&mut central).await.unwrap();
+    devices.retain(|d| d.contains("GoPro"));
+    assert!(!devices.is_empty(), "No GoPro devices found");
+
+    let gopro = connect(devices.first().unwrap().clone(), &mut central)
+        .await
+        .unwrap();
+
+    println!("Connected to GoPro");
+
+    gopro
+        .wait_until_ready(Duration::from_secs(10))
+        .await
+        .unwrap();
+    println!("Starting Recording");
+    let session = gopro.start_recording().await.unwrap();
+
+    time::sleep(Duration::from_secs(3)).await;
+    println!("Adding HiLight");
+    session.add_hilight().await.unwrap();
+
+    time::sleep(Duration::from_secs(2)).await;
+    println!("Elapsed: {:?}", session.elapsed().await.unwrap());
+
+    println!("Stopping Recording");
+    session.stop().await.unwrap();
+
+    println!("Recording for 3 seconds");
+    gopro.record_for(Duration::from_secs(3)).await.unwrap();
+
+    println!("Powering off");
+    gopro.disconnect_and_poweroff().await.unwrap();
+}
+
+#[cfg(feature = "query")]
+#[tokio::test]
+async fn test_take_photo() {
+    let mut central = init(None).await.unwrap();
+    let mut devices = scan(&mut central).await.unwrap();
+    devices.retain(|d| d.contains("GoPro"));
+    assert!(!devices.is_empty(), "No GoPro devices found");
+
+    let gopro = connect(devices.first().unwrap().clone(), &mut central)
+        .await
+        .unwrap();
+
+    println!("Connected to GoPro");
+
+    time::sleep(Duration::from_secs(4)).await;
+    println!("Taking Photo");
+    let first = gopro.take_photo().await.unwrap();
+    println!("Photo taken: {:?}", first);
+
+    println!("Taking Another Photo");
+    let second = gopro.take_photo().await.unwrap();
+    println!("Photo taken: {:?}", second);
+    assert!(second.total_photos > first.total_photos);
+
+    println!("Powering off");
+    gopro.disconnect_and_poweroff().await.unwrap();
+}
+
+#[cfg(feature = "wifi")]
+#[tokio::test]
+async fn test_wifi_ap() {
+    let mut central = init(None).await.unwrap();
+    let mut devices = scan(&mut central).await.unwrap();
+    devices.retain(|d| d.contains("GoPro"));
+    assert!(!devices.is_empty(), "No GoPro devices found");
+
+    let gopro = connect(devices.first().unwrap().clone(), &mut central)
+        .await
+        .unwrap();
+
+    println!("Connected to GoPro");
+
+    time::sleep(Duration::from_secs(4)).await;
+    println!("Enabling WiFi AP");
+    gopro.enable_wifi_ap(true).await.unwrap();
+
+    let credentials = gopro.wifi_ap_credentials().await.unwrap();
+    println!("WiFi AP: {:?}", credentials);
+
+    time::sleep(Duration::from_secs(4)).await;
+    println!("Disabling WiFi AP");
+    gopro.enable_wifi_ap(false).await.unwrap();
+
+    println!("Powering off");
+    gopro.disconnect_and_poweroff().await.unwrap();
+}
+
+#[cfg(feature = "wifi")]
+#[tokio::test]
+async fn test_scan_access_points() {
+    let mut central = init(None).await.unwrap();
+    let mut devices = scan(&mut central).await.unwrap();
+    devices.retain(|d| d.contains("GoPro"));
+    assert!(!devices.is_empty(), "No GoPro devices found");
+
+    let gopro = connect(devices.first().unwrap().clone(), &mut central)
+        .await
+        .unwrap();
+
+    println!("Connected to GoPro");
+
+    println!("Scanning for access points");
+    let ap_scan = gopro.scan_access_points().await.unwrap();
+    println!("Scan finished: {:?}", ap_scan);
+
+    let access_points = gopro
+        .access_points(&ap_scan, 0, ap_scan.total_entries)
+        .await
+        .unwrap();
+    for ap in &access_points {
+        println!("{:?}", ap);
+    }
+    assert_eq!(access_points.len() as u32, ap_scan.total_entries);
+
+    println!("Powering off");
+    gopro.disconnect_and_poweroff().await.unwrap();
+}
+
+#[cfg(feature = "wifi")]
+#[tokio::test]
+async fn test_cohn_status() {
+    let mut central = init(None).await.unwrap();
+    let mut devices = scan(&mut central).await.unwrap();
+    devices.retain(|d| d.contains("GoPro"));
+    assert!(!devices.is_empty(), "No GoPro devices found");
+
+    let gopro = connect(devices.first().unwrap().clone(), &mut central)
+        .await
+        .unwrap();
+
+    println!("Connected to GoPro");
+
+    let status = gopro.cohn_status().await.unwrap();
+    println!("COHN status: {:?}", status);
+
+    if status.provisioned {
+        let certificate = gopro.cohn_certificate().await.unwrap();
+        assert!(certificate.contains("BEGIN CERTIFICATE"));
+    }
+
+    println!("Powering off");
+    gopro.disconnect_and_poweroff().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_list_services() {
+    let mut central = init(None).await.unwrap();
+    let mut devices = scan(&mut central).await.unwrap();
+    devices.retain(|d| d.contains("GoPro"));
+    assert!(!devices.is_empty(), "No GoPro devices found");
+
+    let gopro = connect(devices.first().unwrap().clone(), &mut central)
+        .await
+        .unwrap();
+
+    println!("Connected to GoPro");
+
+    for service in gopro.services() {
+        println!("Service: {}", service.uuid);
+        for characteristic in service.characteristics {
+            println!(
+                "    Characteristic: {} {:?}",
+                characteristic.uuid, characteristic.properties
+            );
+        }
+    }
+
+    let subscribed = gopro.subscribed_characteristics();
+    println!("Subscribed to: {:?}", subscribed);
+    assert!(subscribed.contains(&GPCharac::CommandResponse.to_uuid()));
+
+    gopro.disconnect().await.unwrap();
+}
@@ -0,0 +1,21 @@
+///The credentials for joining the camera's WiFi access point
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiApCredentials {
+    ///The name of the access point
+    pub ssid: String,
+    ///The password of the access point
+    pub password: String,
+}
+
+///The credentials for making HTTPS requests to a camera on the home network (COHN)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CohnCredentials {
+    ///The username for basic authentication
+    pub username: String,
+    ///The password for basic authentication
+    pub password: String,
+    ///The address of the camera on the home network
+    pub ip_address: String,
+    ///The PEM encoded certificate of the authority that signed the camera's certificate
+    pub certificate: String,
+}
@@ -0,0 +1,178 @@
+#!/usr/bin/env python3
+"""Writes GX010001.MP4 and GX020001.MP4, two tiny chapters of the same recording
+laid out the way a GoPro writes them: a video track and a GPMF telemetry track
+("meta" handler, "gpmd" sample entry) interleaved in one mdat, and a udta box with
+the firmware version and the hilights (HMMT) of the chapter.
+
+The video samples are placeholder bytes, the GPMF samples are real KLV telemetry.
+Run from this directory to regenerate the fixtures.
+"""
+import struct
+
+
+def box(kind, payload):
+    return struct.pack(">I", 8 + len(payload)) + kind + payload
+
+
+def full_box(kind, version, flags, payload):
+    return box(kind, struct.pack(">I", (version << 24) | flags) + payload)
+
+
+MATRIX = struct.pack(">9I", 0x10000, 0, 0, 0, 0x10000, 0, 0, 0, 0x40000000)
+
+
+def klv(key, kind, size, repeat, data):
+    data += b"\0" * (-len(data) % 4)
+    return key + kind + struct.pack(">BH", size, repeat) + data
+
+
+def nested(key, *children):
+    data = b"".join(children)
+    return klv(key, b"\0", 1, len(data), data)
+
+
+def string(key, value):
+    return klv(key, b"c", 1, len(value), value)
+
+
+def values(key, kind, fmt, samples):
+    size = struct.calcsize(">" + fmt)
+    data = b"".join(struct.pack(">" + fmt, *sample) for sample in samples)
+    return klv(key, kind, size, len(samples), data)
+
+
+def gpmf(start_us, totals, accl, gps, gyro, temperature):
+    return nested(
+        b"DEVC",
+        values(b"DVID", b"L", "I", [(1,)]),
+        string(b"DVNM", b"HERO11 Black"),
+        nested(
+            b"STRM",
+            values(b"STMP", b"J", "Q", [(start_us,)]),
+            values(b"TSMP", b"L", "I", [(totals[0],)]),
+            string(b"STNM", b"Accelerometer"),
+            string(b"SIUN", b"m/s2"),
+            values(b"SCAL", b"s", "h", [(418,)]),
+            values(b"ACCL", b"s", "hhh", accl),
+        ),
+        nested(
+            b"STRM",
+            values(b"STMP", b"J", "Q", [(start_us,)]),
+            values(b"TSMP", b"L", "I", [(totals[1],)]),
+            string(b"STNM", b"GPS (Lat., Long., Alt., 2D speed, 3D speed)"),
+            values(b"GPSF", b"L", "I", [(3,)]),
+            klv(b"GPSU", b"U", 16, 1, b"231006123456.000"),
+            values(b"GPSP", b"S", "H", [(150,)]),
+            klv(b"SCAL", b"l", 4, 5, struct.pack(">5i", 10000000, 10000000, 1000, 1000, 100)),
+            values(b"GPS5", b"l", "iiiii", gps),
+        ),
+        nested(
+            b"STRM",
+            values(b"STMP", b"J", "Q", [(start_us,)]),
+            values(b"TSMP", b"L", "I", [(totals[2],)]),
+            string(b"STNM", b"Gyroscope"),
+            string(b"SIUN", b"rad/s"),
+            values(b"SCAL", b"s", "h", [(939,)]),
+            values(b"GYRO", b"s", "hhh", gyro),
+        ),
+        nested(
+            b"STRM",
+            values(b"STMP", b"J", "Q", [(start_us,)]),
+            values(b"TSMP", b"L", "I", [(totals[3],)]),
+            string(b"STNM", b"Camera temperature"),
+            values(b"TMPC", b"f", "f", [(temperature,)]),
+        ),
+    )
+
+
+def stbl(entry, stts, sizes, chunks, offsets, stss=None, ctts=None):
+    boxes = [full_box(b"stsd", 0, 0, struct.pack(">I", 1) + entry)]
+    boxes.append(full_box(b"stts", 0, 0, struct.pack(">I", len(stts)) + b"".join(struct.pack(">II", *e) for e in stts)))
+    if ctts is not None:
+        boxes.append(full_box(b"ctts", 0, 0, struct.pack(">I", len(ctts)) + b"".join(struct.pack(">II", *e) for e in ctts)))
+    if stss is not None:
+        boxes.append(full_box(b"stss", 0, 0, struct.pack(">I", len(stss)) + b"".join(struct.pack(">I", n) for n in stss)))
+    boxes.append(full_box(b"stsc", 0, 0, struct.pack(">I", len(chunks)) + b"".join(struct.pack(">III", *e) for e in chunks)))
+    boxes.append(full_box(b"stsz", 0, 0, struct.pack(">II", 0, len(sizes)) + b"".join(struct.pack(">I", s) for s in sizes)))
+    boxes.append(full_box(b"stco", 0, 0, struct.pack(">I", len(offsets)) + b"".join(struct.pack(">I", o) for o in offsets)))
+    return box(b"stbl", b"".join(boxes))
+
+
+def trak(track_id, movie_duration, timescale, duration, handler, name, header, table, width=0, height=0):
+    tkhd = full_box(
+        b"tkhd", 0, 3,
+        struct.pack(">IIIII", 0, 0, track_id, 0, movie_duration) + b"\0" * 8
+        + struct.pack(">hhhH", 0, 0, 0, 0) + MATRIX + struct.pack(">II", width << 16, height << 16),
+    )
+    elst = full_box(b"elst", 0, 0, struct.pack(">IIiI", 1, movie_duration, 0, 0x10000))
+    mdhd = full_box(b"mdhd", 0, 0, struct.pack(">IIIIHH", 0, 0, timescale, duration, 0x55C4, 0))
+    hdlr = full_box(b"hdlr", 0, 0, struct.pack(">I", 0) + handler + b"\0" * 12 + name + b"\0")
+    dinf = box(b"dinf", full_box(b"dref", 0, 0, struct.pack(">I", 1) + full_box(b"url ", 0, 1, b"")))
+    minf = box(b"minf", header + dinf + table)
+    return box(b"trak", tkhd + box(b"edts", elst) + box(b"mdia", mdhd + hdlr + minf))
+
+
+AVC1 = box(
+    b"avc1",
+    b"\0" * 6 + struct.pack(">H", 1) + b"\0" * 16 + struct.pack(">HHIII", 64, 48, 0x480000, 0x480000, 0)
+    + struct.pack(">H", 1) + b"\0" * 32 + struct.pack(">Hh", 0x18, -1)
+    + box(b"avcC", bytes([1, 0x64, 0, 0x1F, 0xFF, 0xE0, 0])),
+)
+GPMD = box(b"gpmd", b"\0" * 6 + struct.pack(">H", 1) + b"\0" * 4)
+
+
+def hmmt(hilights):
+    # The camera reserves room for 100 hilights, in milliseconds from the start of the chapter
+    slots = hilights + [0] * (100 - len(hilights))
+    return box(b"HMMT", struct.pack(">I", len(hilights)) + struct.pack(">100I", *slots))
+
+
+def chapter(number, frames, movie_duration, telemetry, hilights):
+    ftyp = box(b"ftyp", b"mp41" + struct.pack(">I", 0x13000000) + b"mp41")
+    video = [b"VIDEO %d.%d " % (number, i) * (4 - i) for i in range(frames)]
+    # The first chunk holds all but the last frame, then the telemetry, then the last frame
+    payload = b"".join(video[:-1]) + telemetry + video[-1]
+    start = len(ftyp) + 8
+    video_offsets = [start, start + sum(map(len, video[:-1])) + len(telemetry)]
+    gpmf_offset = start + sum(map(len, video[:-1]))
+    mdat = box(b"mdat", payload)
+
+    mvhd = full_box(
+        b"mvhd", 0, 0,
+        struct.pack(">IIIIIH", 0, 0, 1000, movie_duration, 0x10000, 0x100) + b"\0" * 10
+        + MATRIX + b"\0" * 24 + struct.pack(">I", 3),
+    )
+    video_trak = trak(
+        1, movie_duration, 90000, frames * 30000, b"vide", b"GoPro AVC",
+        full_box(b"vmhd", 0, 1, b"\0" * 8),
+        stbl(AVC1, [(frames, 30000)], [len(v) for v in video],
+             [(1, frames - 1, 1), (2, 1, 1)], video_offsets, stss=[1], ctts=[(frames, 0)]),
+        64, 48,
+    )
+    gpmf_trak = trak(
+        2, movie_duration, 1000, movie_duration, b"meta", b"GoPro MET",
+        full_box(b"nmhd", 0, 0, b""),
+        stbl(GPMD, [(1, movie_duration)], [len(telemetry)], [(1, 1, 1)], [gpmf_offset]),
+    )
+    udta = box(b"udta", box(b"FIRM", b"H22.01.01.10.70") + hmmt(hilights))
+    return ftyp + mdat + box(b"moov", mvhd + video_trak + gpmf_trak + udta)
+
+
+first = gpmf(
+    0, (3, 2, 3, 1),
+    [(4180, -418, 836), (4180, 0, 0), (4598, 418, -418)],
+    [(475012345, -1224567890, 12345, 1500, 160), (475012400, -1224567800, 12400, 1510, 161)],
+    [(939, 0, -939), (0, 0, 0), (1878, 939, 0)],
+    45.5,
+)
+second = gpmf(
+    1000000, (5, 3, 5, 2),
+    [(4180, 0, 418), (4180, 0, 0)],
+    [(475012500, -1224567700, 12500, 1520, 162)],
+    [(0, 939, 0), (0, 0, 939)],
+    46.0,
+)
+with open("GX010001.MP4", "wb") as f:
+    f.write(chapter(1, 3, 1000, first, [250, 800]))
+with open("GX020001.MP4", "wb") as f:
+    f.write(chapter(2, 2, 667, second, [300]))